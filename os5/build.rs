@@ -1,10 +1,28 @@
-use std::fs::{read_dir, File};
+use std::env;
+use std::fs::{copy, read_dir, File};
 use std::io::{Result, Write};
+use std::path::{Path, PathBuf};
 
 fn main() {
     println!("cargo:rerun-if-changed=../user/src/");
     println!("cargo:rerun-if-changed={}", TARGET_PATH);
+    println!("cargo:rerun-if-changed={}", KSYMS_PATH);
     insert_app_data().unwrap();
+    insert_ksyms().unwrap();
+}
+
+static KSYMS_PATH: &str = "target/ksyms.S";
+
+/// Link in the symbol table `scripts/ksyms.py` wrote for the last build, if
+/// any; without `cfg(ksyms)` the kernel has an empty one.
+fn insert_ksyms() -> Result<()> {
+    if !Path::new(KSYMS_PATH).exists() {
+        return Ok(());
+    }
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    copy(KSYMS_PATH, out_dir.join("ksyms.S"))?;
+    println!("cargo:rustc-cfg=ksyms");
+    Ok(())
 }
 
 static TARGET_PATH: &str = "../user/build/elf/";
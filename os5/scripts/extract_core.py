@@ -0,0 +1,54 @@
+#!/usr/bin/env python3
+"""Turn the hex core dumps printed by ch5b_coredump back into ELF core files.
+
+Save the console output of a QEMU run, then
+
+    python3 scripts/extract_core.py console.log
+
+writes core.<pid> for every dump found. Load one into gdb along with the app
+it came from:
+
+    riscv64-unknown-elf-gdb ../user/target/riscv64gc-unknown-none-elf/release/<app> core.<pid>
+"""
+
+import re
+import sys
+
+BEGIN = re.compile(r"-----BEGIN CORE (\d+) (\S+)-----")
+END = "-----END CORE-----"
+
+
+def extract(lines):
+    """Yield (pid, app, bytes) for every dump in `lines`."""
+    dump = None
+    for line in lines:
+        line = line.strip()
+        match = BEGIN.search(line)
+        if match:
+            dump = (int(match.group(1)), match.group(2), bytearray())
+        elif dump is not None and line == END:
+            yield dump
+            dump = None
+        elif dump is not None:
+            dump[2].extend(bytes.fromhex(line))
+
+
+def main():
+    if len(sys.argv) != 2:
+        sys.exit(f"usage: {sys.argv[0]} CONSOLE_LOG")
+    with open(sys.argv[1], errors="replace") as log:
+        dumps = list(extract(log))
+    if not dumps:
+        sys.exit("no core dump found")
+    for pid, app, data in dumps:
+        if data[:4] != b"\x7fELF":
+            print(f"core of {app} (pid {pid}) is damaged, skipped", file=sys.stderr)
+            continue
+        path = f"core.{pid}"
+        with open(path, "wb") as core:
+            core.write(data)
+        print(f"{path}: {app}, {len(data)} bytes")
+
+
+if __name__ == "__main__":
+    main()
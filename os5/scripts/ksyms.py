@@ -0,0 +1,124 @@
+#!/usr/bin/env python3
+"""Generate the kernel symbol table that panic backtraces are named with.
+
+    python3 scripts/ksyms.py target/riscv64gc-unknown-none-elf/release/os target/ksyms.S
+
+reads the functions in the .symtab of the kernel and writes them, sorted and
+demangled, as assembly for src/ksyms.rs. The table sits in .rodata, after the
+code, so linking it in moves no function. The Makefile builds again until
+the table stops changing, and fails if it still changes after the last
+build: this script exits with 0 if target/ksyms.S was up to date already, with
+1 if it has rewritten it.
+"""
+
+import re
+import struct
+import sys
+
+SHT_SYMTAB = 2
+STT_FUNC = 2
+
+HASH = re.compile(r"^h[0-9a-f]{16}$")
+ESCAPES = {
+    "SP": "@",
+    "BP": "*",
+    "RF": "&",
+    "LT": "<",
+    "GT": ">",
+    "LP": "(",
+    "RP": ")",
+    "C": ",",
+}
+
+
+def functions(elf):
+    """Yield (address, size, name) for every function in `elf`."""
+    assert elf[:4] == b"\x7fELF" and elf[4] == 2, "not an ELF64 file"
+    shoff, = struct.unpack_from("<Q", elf, 0x28)
+    shentsize, shnum = struct.unpack_from("<HH", elf, 0x3A)
+    sections = [
+        struct.unpack_from("<IIQQQQIIQQ", elf, shoff + i * shentsize)
+        for i in range(shnum)
+    ]
+    for _, kind, _, _, offset, size, link, _, _, entsize in sections:
+        if kind != SHT_SYMTAB:
+            continue
+        strtab = sections[link][4]
+        for entry in range(offset, offset + size, entsize):
+            name, info, _, _, value, size = struct.unpack_from("<IBBHQQ", elf, entry)
+            if info & 0xF == STT_FUNC and size > 0:
+                end = elf.index(b"\0", strtab + name)
+                yield value, size, elf[strtab + name:end].decode()
+
+
+def unescape(ident):
+    if ident.startswith("_$"):
+        ident = ident[1:]
+    ident = ident.replace("..", "::")
+
+    def replace(match):
+        escape = match.group(1)
+        if escape in ESCAPES:
+            return ESCAPES[escape]
+        if escape.startswith("u"):
+            return chr(int(escape[1:], 16))
+        return match.group(0)
+
+    return re.sub(r"\$([A-Za-z0-9]+)\$", replace, ident)
+
+
+def demangle(name):
+    """Undo the legacy Rust mangling, dropping the hash."""
+    if not (name.startswith("_ZN") and name.endswith("E")):
+        return name
+    rest = name[3:-1]
+    idents = []
+    while rest:
+        match = re.match(r"\d+", rest)
+        if not match:
+            return name
+        end = match.end() + int(match.group(0))
+        idents.append(rest[match.end():end])
+        rest = rest[end:]
+    if idents and HASH.match(idents[-1]):
+        idents.pop()
+    return "::".join(unescape(ident) for ident in idents)
+
+
+def generate(elf):
+    symbols = sorted(set(functions(elf)))
+    lines = [
+        "# generated by scripts/ksyms.py, do not edit",
+        "    .section .rodata.ksyms",
+        "    .align 3",
+        "    .global _ksyms",
+        "_ksyms:",
+        "    .quad {}".format(len(symbols)),
+    ]
+    lines += ["    .quad {:#x}".format(address) for address, _, _ in symbols]
+    offset = 0
+    for _, size, name in symbols:
+        lines.append("    .word {:#x}, {:#x}".format(size, offset))
+        offset += len(demangle(name).encode()) + 1
+    lines += ['    .string "{}"'.format(demangle(name).replace('"', '\\"'))
+              for _, _, name in symbols]
+    return "\n".join(lines) + "\n"
+
+
+def main():
+    elf_path, out_path = sys.argv[1:3]
+    with open(elf_path, "rb") as f:
+        table = generate(f.read())
+    try:
+        with open(out_path) as f:
+            if f.read() == table:
+                return 0
+    except FileNotFoundError:
+        pass
+    with open(out_path, "w") as f:
+        f.write(table)
+    return 1
+
+
+if __name__ == "__main__":
+    sys.exit(main())
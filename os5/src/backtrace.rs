@@ -0,0 +1,71 @@
+//! Backtraces of the kernel, for panics and traps it can't handle
+//!
+//! The kernel is built with frame pointers: each frame keeps the return
+//! address at `fp - 8` and the frame pointer of its caller at `fp - 16`.
+//! Only frames on the current kernel stack are followed, so that a broken
+//! chain, or the user frame pointer left in `s0` by `__alltraps`, ends the
+//! walk instead of faulting again.
+
+use crate::config::KERNEL_STACK_SIZE;
+use crate::ksyms;
+use core::arch::asm;
+
+/// frames printed at most
+const MAX_FRAMES: usize = 32;
+
+/// Print the callers of the function calling this one.
+#[inline(never)]
+pub fn print_backtrace() {
+    let fp: usize;
+    unsafe {
+        asm!("mv {}, s0", out(reg) fp);
+    }
+    println!("[kernel] Backtrace:");
+    walk(0, fp);
+}
+
+/// Print the frames of the code interrupted at `pc`, whose frame pointer
+/// was `fp`.
+pub fn print_trap_backtrace(pc: usize, fp: usize) {
+    println!("[kernel] Backtrace:");
+    print_frame(0, pc, false);
+    walk(1, fp);
+}
+
+fn walk(mut depth: usize, mut fp: usize) {
+    let sp: usize;
+    unsafe {
+        asm!("mv {}, sp", out(reg) sp);
+    }
+    // kernel stacks are no larger than this, and the boot stack smaller
+    let top = sp.saturating_add(KERNEL_STACK_SIZE);
+    while fp % 8 == 0 && fp >= sp + 16 && fp <= top {
+        if depth == MAX_FRAMES {
+            println!("[kernel]   ...");
+            return;
+        }
+        let (ra, caller_fp) = unsafe { (*((fp - 8) as *const usize), *((fp - 16) as *const usize)) };
+        if ra == 0 {
+            return;
+        }
+        print_frame(depth, ra, true);
+        depth += 1;
+        // the stack grows down, callers are further up
+        if caller_fp <= fp {
+            return;
+        }
+        fp = caller_fp;
+    }
+}
+
+fn print_frame(depth: usize, pc: usize, is_return_address: bool) {
+    // a return address may be past the end of a caller which does not
+    // return itself
+    let lookup = if is_return_address { pc - 1 } else { pc };
+    match ksyms::lookup(lookup) {
+        Some((name, start)) => {
+            println!("[kernel]   #{:<2} {:#018x} in {}+{:#x}", depth, pc, name, pc - start)
+        }
+        None => println!("[kernel]   #{:<2} {:#018x}", depth, pc),
+    }
+}
@@ -2,11 +2,25 @@ pub const USER_STACK_SIZE: usize = 4096 * 2;
 pub const KERNEL_STACK_SIZE: usize = 4096 * 20;
 pub const KERNEL_HEAP_SIZE: usize = 0x30_0000;
 pub const MEMORY_END: usize = 0x88000000;
+/// where poisoned user pages point, past the memory of the machine and so
+/// left out of the kernel map, see [`crate::mm::MemorySet::poison`]
+pub const POISON_PA: usize = MEMORY_END;
 pub const PAGE_SIZE: usize = 0x1000;
 pub const PAGE_SIZE_BITS: usize = 0xc;
+/// a Sv39 megapage spans `1 << HUGE_PAGE_ORDER` pages
+pub const HUGE_PAGE_ORDER: usize = 9;
+pub const HUGE_PAGE_PAGES: usize = 1 << HUGE_PAGE_ORDER;
+pub const HUGE_PAGE_SIZE: usize = PAGE_SIZE << HUGE_PAGE_ORDER;
 pub const MAX_SYSCALL_NUM: usize = 500;
 pub const BIG_STRIDE: usize = usize::MAX;
 
 pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
 pub const TRAP_CONTEXT: usize = TRAMPOLINE - PAGE_SIZE;
+/// the read-only time page of every process, see [`crate::vdso`]
+pub const VDSO: usize = TRAP_CONTEXT - PAGE_SIZE;
+/// where the syscall ring is mapped, the last MiB of the lower half of Sv39
+pub const RING_BASE: usize = (1 << 38) - 0x10_0000;
 pub const CLOCK_FREQ: usize = 12500000;
+/// Goldfish RTC of the QEMU virt machine
+pub const VIRT_RTC: usize = 0x101000;
+pub const MMIO: &[(usize, usize)] = &[(VIRT_RTC, 0x1000)];
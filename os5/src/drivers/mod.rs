@@ -0,0 +1,3 @@
+mod rtc;
+
+pub use rtc::read_rtc_ns;
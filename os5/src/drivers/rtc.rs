@@ -0,0 +1,16 @@
+//! Goldfish real-time clock of the QEMU virt machine
+
+use crate::config::VIRT_RTC;
+
+/// low 32 bits of the time, reading them latches the high ones
+const TIME_LOW: usize = 0x00;
+const TIME_HIGH: usize = 0x04;
+
+/// Wall-clock time in nanoseconds since the epoch.
+pub fn read_rtc_ns() -> u64 {
+    unsafe {
+        let low = ((VIRT_RTC + TIME_LOW) as *const u32).read_volatile();
+        let high = ((VIRT_RTC + TIME_HIGH) as *const u32).read_volatile();
+        ((high as u64) << 32) | low as u64
+    }
+}
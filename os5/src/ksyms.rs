@@ -0,0 +1,56 @@
+//! The kernel symbol table, for naming the frames of a backtrace
+//!
+//! `scripts/ksyms.py` generates `target/ksyms.S` from the linked kernel: the
+//! count of functions, their sorted start addresses, then the size and name
+//! offset of each as two `u32`, then the nul-terminated demangled names.
+//! `build.rs` copies it into `OUT_DIR` and sets `cfg(ksyms)`; until the
+//! Makefile has run the script, or with another build script, the table is
+//! empty.
+
+#[cfg(ksyms)]
+core::arch::global_asm!(include_str!(concat!(env!("OUT_DIR"), "/ksyms.S")));
+
+#[cfg(not(ksyms))]
+core::arch::global_asm!(
+    "
+    .section .rodata.ksyms
+    .align 3
+    .global _ksyms
+_ksyms:
+    .quad 0"
+);
+
+fn table() -> (&'static [usize], &'static [[u32; 2]], *const u8) {
+    extern "C" {
+        fn _ksyms();
+    }
+    let count_ptr = _ksyms as usize as *const usize;
+    unsafe {
+        let count = count_ptr.read_volatile();
+        let starts = core::slice::from_raw_parts(count_ptr.add(1), count);
+        let entries_ptr = count_ptr.add(1 + count) as *const [u32; 2];
+        let entries = core::slice::from_raw_parts(entries_ptr, count);
+        (starts, entries, entries_ptr.add(count) as *const u8)
+    }
+}
+
+/// The function `addr` is in and its start address.
+pub fn lookup(addr: usize) -> Option<(&'static str, usize)> {
+    let (starts, entries, names) = table();
+    let index = starts
+        .partition_point(|&start| start <= addr)
+        .checked_sub(1)?;
+    let [size, name_offset] = entries[index];
+    if addr >= starts[index] + size as usize {
+        return None;
+    }
+    unsafe {
+        let name = names.add(name_offset as usize);
+        let mut len = 0;
+        while name.add(len).read() != 0 {
+            len += 1;
+        }
+        let name = core::slice::from_raw_parts(name, len);
+        Some((core::str::from_utf8(name).ok()?, starts[index]))
+    }
+}
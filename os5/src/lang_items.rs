@@ -1,5 +1,11 @@
+use crate::backtrace::print_backtrace;
 use crate::sbi::shutdown;
 use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// set by the first panic, so that one while printing the backtrace does
+/// not print it again
+static PANICKING: AtomicBool = AtomicBool::new(false);
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
@@ -13,5 +19,8 @@ fn panic(info: &PanicInfo) -> ! {
     } else {
         println!("[kernel] Panicked: {}", info.message().unwrap());
     }
+    if !PANICKING.swap(true, Ordering::Relaxed) {
+        print_backtrace();
+    }
     shutdown()
 }
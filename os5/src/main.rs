@@ -12,7 +12,10 @@ extern crate alloc;
 
 #[macro_use]
 mod console;
+mod backtrace;
 mod config;
+mod drivers;
+mod ksyms;
 mod lang_items;
 mod loader;
 mod logging;
@@ -23,6 +26,7 @@ mod syscall;
 mod task;
 mod timer;
 mod trap;
+mod vdso;
 
 core::arch::global_asm!(include_str!("entry.asm"));
 core::arch::global_asm!(include_str!("link_app.S"));
@@ -43,8 +47,11 @@ pub fn rust_main() -> ! {
     clear_bss();
     logging::init();
     println!("[kernel] Hello, world!");
+    timer::init();
     mm::init();
     mm::remap_test();
+    timer::init_realtime();
+    vdso::init();
     task::add_initproc();
     info!("after initproc!");
     trap::init();
@@ -0,0 +1,122 @@
+//! Implementation of [`AsidAllocator`] which hands out the address space
+//! identifiers tagging TLB entries of each [`super::MemorySet`].
+//!
+//! ASID 0 belongs to the kernel space. It is also handed out to user spaces
+//! when the hardware implements no ASID bits or all of them are in use, and
+//! such spaces get their TLB entries flushed on every switch in `trap.S`.
+
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
+use lazy_static::*;
+use riscv::register::satp;
+
+/// width of the ASID field in Sv39 `satp`
+const ASID_BITS: usize = 16;
+const ASID_SHIFT: usize = 44;
+
+/// an ASID which has the same lifecycle as the handle
+pub struct AsidHandle(pub usize);
+
+impl AsidHandle {
+    /// the ASID of the kernel space, never recycled
+    pub fn kernel() -> Self {
+        Self(0)
+    }
+}
+
+impl Drop for AsidHandle {
+    fn drop(&mut self) {
+        if self.0 != 0 {
+            ASID_ALLOCATOR.exclusive_access().dealloc(self.0);
+        }
+    }
+}
+
+struct AsidAllocator {
+    current: usize,
+    /// largest ASID the hardware supports
+    max: usize,
+    recycled: Vec<usize>,
+}
+
+impl AsidAllocator {
+    pub fn new() -> Self {
+        AsidAllocator {
+            current: 1,
+            max: 0,
+            recycled: Vec::new(),
+        }
+    }
+
+    pub fn alloc(&mut self) -> AsidHandle {
+        let asid = if let Some(asid) = self.recycled.pop() {
+            asid
+        } else if self.current <= self.max {
+            self.current += 1;
+            self.current - 1
+        } else {
+            return AsidHandle(0);
+        };
+        // drop whatever the previous owner left in the TLB
+        sfence_vma_asid(asid);
+        AsidHandle(asid)
+    }
+
+    pub fn dealloc(&mut self, asid: usize) {
+        assert!(asid < self.current);
+        assert!(
+            !self.recycled.iter().any(|a| *a == asid),
+            "asid {} has been deallocated!", asid
+        );
+        self.recycled.push(asid);
+    }
+}
+
+lazy_static! {
+    static ref ASID_ALLOCATOR: UPSafeCell<AsidAllocator> = unsafe {
+        UPSafeCell::new(AsidAllocator::new())
+    };
+}
+
+/// Find out how many ASID bits are implemented by writing all ones to the
+/// field, which is WARL. Must be called with paging enabled.
+pub fn init_asid() {
+    let token = satp::read().bits();
+    unsafe {
+        satp::write(token | ((1 << ASID_BITS) - 1) << ASID_SHIFT);
+    }
+    let max = satp::read().bits() >> ASID_SHIFT & ((1 << ASID_BITS) - 1);
+    unsafe {
+        satp::write(token);
+    }
+    ASID_ALLOCATOR.exclusive_access().max = max;
+    info!("ASID max: {}", max);
+}
+
+pub fn asid_alloc() -> AsidHandle {
+    ASID_ALLOCATOR.exclusive_access().alloc()
+}
+
+/// the ASID field of a `satp` value
+pub fn token_asid(token: usize) -> usize {
+    token >> ASID_SHIFT & ((1 << ASID_BITS) - 1)
+}
+
+/// make an Sv39 `satp` value
+pub fn make_token(asid: usize, root_ppn: usize) -> usize {
+    8usize << 60 | asid << ASID_SHIFT | root_ppn
+}
+
+/// flush the TLB entries of a single page of an address space
+pub fn sfence_vma(va: usize, asid: usize) {
+    unsafe {
+        core::arch::asm!("sfence.vma {}, {}", in(reg) va, in(reg) asid);
+    }
+}
+
+/// flush all the TLB entries of an address space
+pub fn sfence_vma_asid(asid: usize) {
+    unsafe {
+        core::arch::asm!("sfence.vma zero, {}", in(reg) asid);
+    }
+}
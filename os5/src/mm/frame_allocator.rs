@@ -4,35 +4,47 @@
 use super::{PhysAddr, PhysPageNum};
 use crate::config::MEMORY_END;
 use crate::sync::UPSafeCell;
+use alloc::collections::BTreeSet;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug, Formatter};
 use lazy_static::*;
 
-/// manage a frame which has the same lifecycle as the tracker
+/// manage `1 << order` contiguous frames which have the same lifecycle as the tracker
 pub struct FrameTracker {
     pub ppn: PhysPageNum,
+    pub order: usize,
 }
 
 impl FrameTracker {
     pub fn new(ppn: PhysPageNum) -> Self {
+        Self::with_order(ppn, 0)
+    }
+    pub fn with_order(ppn: PhysPageNum, order: usize) -> Self {
         // page cleaning
-        let bytes_array = ppn.get_bytes_array();
-        for i in bytes_array {
-            *i = 0;
+        for i in 0..(1 << order) {
+            PhysPageNum(ppn.0 + i).get_bytes_array().fill(0);
         }
-        Self { ppn }
+        Self { ppn, order }
+    }
+    /// number of frames held by the tracker
+    pub fn pages(&self) -> usize {
+        1 << self.order
     }
 }
 
 impl Debug for FrameTracker {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_fmt(format_args!("FrameTracker:PPN={:#x}", self.ppn.0))
+        f.write_fmt(format_args!(
+            "FrameTracker:PPN={:#x},order={}",
+            self.ppn.0, self.order
+        ))
     }
 }
 
 impl Drop for FrameTracker {
     fn drop(&mut self) {
-        frame_dealloc(self.ppn);
+        frame_dealloc_contig(self.ppn, self.order);
     }
 }
 
@@ -40,17 +52,28 @@ trait FrameAllocator {
     fn new() -> Self;
     fn alloc(&mut self) -> Option<PhysPageNum>;
     fn dealloc(&mut self, ppn: PhysPageNum);
+    /// allocate `1 << order` contiguous frames aligned to their size
+    fn alloc_contig(&mut self, order: usize) -> Option<PhysPageNum>;
+    fn dealloc_contig(&mut self, ppn: PhysPageNum, order: usize);
+    /// number of frames under management
+    fn total(&self) -> usize;
+    /// number of frames that can still be allocated
+    fn free(&self) -> usize;
 }
 
+#[allow(unused)]
 /// an implementation for frame allocator
 pub struct StackFrameAllocator {
+    start: usize,
     current: usize,
     end: usize,
     recycled: Vec<usize>,
 }
 
+#[allow(unused)]
 impl StackFrameAllocator {
     pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum) {
+        self.start = l.0;
         self.current = l.0;
         self.end = r.0;
     }
@@ -58,6 +81,7 @@ impl StackFrameAllocator {
 impl FrameAllocator for StackFrameAllocator {
     fn new() -> Self {
         Self {
+            start: 0,
             current: 0,
             end: 0,
             recycled: Vec::new(),
@@ -82,9 +106,136 @@ impl FrameAllocator for StackFrameAllocator {
         // recycle
         self.recycled.push(ppn);
     }
+    /// Only bump allocation from `current` can be contiguous, the frames
+    /// skipped for alignment are recycled.
+    fn alloc_contig(&mut self, order: usize) -> Option<PhysPageNum> {
+        if order == 0 {
+            return self.alloc();
+        }
+        let size = 1 << order;
+        let start = (self.current + size - 1) & !(size - 1);
+        if start + size > self.end {
+            return None;
+        }
+        self.recycled.extend(self.current..start);
+        self.current = start + size;
+        Some(start.into())
+    }
+    fn dealloc_contig(&mut self, ppn: PhysPageNum, order: usize) {
+        for i in 0..(1 << order) {
+            self.dealloc(PhysPageNum(ppn.0 + i));
+        }
+    }
+    fn total(&self) -> usize {
+        self.end - self.start
+    }
+    fn free(&self) -> usize {
+        self.end - self.current + self.recycled.len()
+    }
+}
+
+/// largest block the buddy allocator manages, `1 << MAX_ORDER` frames
+const MAX_ORDER: usize = 10;
+
+#[allow(unused)]
+/// a buddy system frame allocator
+pub struct BuddyFrameAllocator {
+    start: usize,
+    end: usize,
+    /// first ppn of the free blocks of each order
+    free_lists: Vec<BTreeSet<usize>>,
+    /// one bit per frame, set at the first frame of each allocated block
+    allocated: Vec<u64>,
+    free: usize,
+}
+
+#[allow(unused)]
+impl BuddyFrameAllocator {
+    pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum) {
+        self.start = l.0;
+        self.end = r.0;
+        self.allocated = vec![0; (r.0 - l.0 + 63) / 64];
+        self.free = r.0 - l.0;
+        // split [l, r) into the largest naturally aligned blocks
+        let mut current = l.0;
+        while current < r.0 {
+            let mut order = MAX_ORDER;
+            while current & ((1 << order) - 1) != 0 || current + (1 << order) > r.0 {
+                order -= 1;
+            }
+            self.free_lists[order].insert(current);
+            current += 1 << order;
+        }
+    }
+    /// flip the allocated bit of block `ppn`, returning its old value
+    fn toggle_allocated(&mut self, ppn: usize) -> bool {
+        let idx = ppn - self.start;
+        let mask = 1u64 << (idx % 64);
+        let word = &mut self.allocated[idx / 64];
+        *word ^= mask;
+        *word & mask == 0
+    }
+}
+impl FrameAllocator for BuddyFrameAllocator {
+    fn new() -> Self {
+        Self {
+            start: 0,
+            end: 0,
+            free_lists: vec![BTreeSet::new(); MAX_ORDER + 1],
+            allocated: Vec::new(),
+            free: 0,
+        }
+    }
+    fn alloc(&mut self) -> Option<PhysPageNum> {
+        self.alloc_contig(0)
+    }
+    fn dealloc(&mut self, ppn: PhysPageNum) {
+        self.dealloc_contig(ppn, 0);
+    }
+    fn alloc_contig(&mut self, order: usize) -> Option<PhysPageNum> {
+        let mut current_order = (order..=MAX_ORDER).find(|&o| !self.free_lists[o].is_empty())?;
+        let block = *self.free_lists[current_order].iter().next().unwrap();
+        self.free_lists[current_order].remove(&block);
+        // give the upper halves back until the block has the requested size
+        while current_order > order {
+            current_order -= 1;
+            self.free_lists[current_order].insert(block + (1 << current_order));
+        }
+        self.toggle_allocated(block);
+        self.free -= 1 << order;
+        Some(block.into())
+    }
+    fn dealloc_contig(&mut self, ppn: PhysPageNum, order: usize) {
+        let mut block = ppn.0;
+        // validity check
+        if block < self.start || block >= self.end || !self.toggle_allocated(block) {
+            panic!("Frame ppn={:#x} has not been allocated!", block);
+        }
+        self.free += 1 << order;
+        // merge with the buddy as long as it is free as a whole
+        let mut current_order = order;
+        while current_order < MAX_ORDER {
+            let buddy = block ^ (1 << current_order);
+            if !self.free_lists[current_order].remove(&buddy) {
+                break;
+            }
+            block = block.min(buddy);
+            current_order += 1;
+        }
+        self.free_lists[current_order].insert(block);
+    }
+    fn total(&self) -> usize {
+        self.end - self.start
+    }
+    fn free(&self) -> usize {
+        self.free
+    }
 }
 
+#[cfg(not(feature = "buddy-frame-allocator"))]
 type FrameAllocatorImpl = StackFrameAllocator;
+#[cfg(feature = "buddy-frame-allocator")]
+type FrameAllocatorImpl = BuddyFrameAllocator;
 
 lazy_static! {
     /// frame allocator instance through lazy_static!
@@ -111,9 +262,23 @@ pub fn frame_alloc() -> Option<FrameTracker> {
         .map(FrameTracker::new)
 }
 
-/// deallocate a frame
-fn frame_dealloc(ppn: PhysPageNum) {
-    FRAME_ALLOCATOR.exclusive_access().dealloc(ppn);
+/// allocate `1 << order` physically contiguous frames
+pub fn frame_alloc_contig(order: usize) -> Option<FrameTracker> {
+    FRAME_ALLOCATOR
+        .exclusive_access()
+        .alloc_contig(order)
+        .map(|ppn| FrameTracker::with_order(ppn, order))
+}
+
+/// deallocate `1 << order` contiguous frames
+fn frame_dealloc_contig(ppn: PhysPageNum, order: usize) {
+    FRAME_ALLOCATOR.exclusive_access().dealloc_contig(ppn, order);
+}
+
+/// total and free frame counts
+pub fn frame_stats() -> (usize, usize) {
+    let allocator = FRAME_ALLOCATOR.exclusive_access();
+    (allocator.total(), allocator.free())
 }
 
 #[allow(unused)]
@@ -134,3 +299,19 @@ pub fn frame_allocator_test() {
     drop(v);
     info!("frame_allocator_test passed!");
 }
+
+/// a simple test for contiguous allocation, run at boot
+pub fn frame_allocator_contig_test() {
+    let order = 3;
+    let block = frame_alloc_contig(order).unwrap();
+    info!("{:?}", block);
+    assert_eq!(block.ppn.0 % block.pages(), 0);
+    let ppn = block.ppn;
+    drop(block);
+    let (total, free) = frame_stats();
+    let block = frame_alloc_contig(order).unwrap();
+    assert_eq!(frame_stats(), (total, free - (1 << order)));
+    drop(block);
+    assert_eq!(frame_stats(), (total, free));
+    info!("frame_allocator_contig_test passed! first block at {:?}", ppn);
+}
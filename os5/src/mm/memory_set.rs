@@ -1,10 +1,16 @@
 //! Implementation of [`MapArea`] and [`MemorySet`]
 
-use super::{frame_alloc, FrameTracker};
+use super::asid::{asid_alloc, sfence_vma_asid, AsidHandle};
+use super::{frame_alloc, frame_alloc_contig, FrameTracker};
 use super::{PTEFlags, PageTable, PageTableEntry};
 use super::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
 use super::{StepByOne, VPNRange};
-use crate::config::{MEMORY_END, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT, USER_STACK_SIZE};
+use crate::config::{
+    HUGE_PAGE_ORDER, HUGE_PAGE_PAGES, MEMORY_END, MMIO, PAGE_SIZE, POISON_PA, TRAMPOLINE,
+    TRAP_CONTEXT, VDSO,
+};
+use crate::trap::copy_from_nofault;
+use crate::vdso::vdso_ppn;
 use crate::sync::UPSafeCell;
 use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
@@ -36,14 +42,22 @@ lazy_static! {
 pub struct MemorySet {
     page_table: PageTable,
     areas: Vec<MapArea>,
+    /// released only after the page table is gone
+    _asid: AsidHandle,
 }
 
+/// Constructors and mappings return `None` when frames run out, leaving
+/// nothing half mapped behind.
 impl MemorySet {
-    pub fn new_bare() -> Self {
-        Self {
-            page_table: PageTable::new(),
+    pub fn new_bare() -> Option<Self> {
+        Self::with_asid(asid_alloc())
+    }
+    fn with_asid(asid: AsidHandle) -> Option<Self> {
+        Some(Self {
+            page_table: PageTable::new(asid.0)?,
             areas: Vec::new(),
-        }
+            _asid: asid,
+        })
     }
     pub fn token(&self) -> usize {
         self.page_table.token()
@@ -54,29 +68,34 @@ impl MemorySet {
         start_va: VirtAddr,
         end_va: VirtAddr,
         permission: MapPermission,
-    ) {
+    ) -> Option<()> {
         self.push(
             MapArea::new(start_va, end_va, MapType::Framed, permission),
             None,
-        );
+        )
     }
 
-    pub fn from_existed_user(user_space: &MemorySet) -> MemorySet {
-        let mut memory_set = Self::new_bare();
+    pub fn from_existed_user(user_space: &MemorySet) -> Option<MemorySet> {
+        let mut memory_set = Self::new_bare()?;
 
-        memory_set.map_trampoline();
+        memory_set.map_trampoline()?;
+        memory_set.map_vdso()?;
 
         for area in user_space.areas.iter() {
             let new_area = MapArea::from_another(area);
-            memory_set.push(new_area, None);
+            memory_set.push(new_area, None)?;
 
             for vpn in area.vpn_range {
-                let src_ppn = user_space.translate(vpn).unwrap().ppn();
+                let src_pa: PhysAddr = user_space.translate(vpn).unwrap().ppn().into();
                 let dst_ppn = memory_set.translate(vpn).unwrap().ppn();
-                dst_ppn.get_bytes_array().copy_from_slice(src_ppn.get_bytes_array());
+                // a poisoned page stays so in the child
+                if !copy_from_nofault(dst_ppn.get_bytes_array(), src_pa.0) {
+                    let new_area = memory_set.areas.last_mut().unwrap();
+                    new_area.poison_one(&mut memory_set.page_table, vpn);
+                }
             }
         }
-        memory_set
+        Some(memory_set)
     }
 
     pub fn remove_area_with_start_vpn(&mut self, start_vpn: VirtPageNum) {
@@ -91,26 +110,36 @@ impl MemorySet {
         }
     }
     
-    fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
-        map_area.map(&mut self.page_table);
+    fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) -> Option<()> {
+        map_area.map(&mut self.page_table)?;
         if let Some(data) = data {
             map_area.copy_data(&mut self.page_table, data);
         }
         self.areas.push(map_area);
+        Some(())
     }
     /// Mention that trampoline is not collected by areas.
-    fn map_trampoline(&mut self) {
+    fn map_trampoline(&mut self) -> Option<()> {
         self.page_table.map(
             VirtAddr::from(TRAMPOLINE).into(),
             PhysAddr::from(strampoline as usize).into(),
             PTEFlags::R | PTEFlags::X,
-        );
+        )
+    }
+    /// The time page is shared by all user spaces and not collected by
+    /// areas either.
+    fn map_vdso(&mut self) -> Option<()> {
+        self.page_table.map(
+            VirtAddr::from(VDSO).into(),
+            vdso_ppn(),
+            PTEFlags::R | PTEFlags::U,
+        )
     }
     /// Without kernel stacks.
     pub fn new_kernel() -> Self {
-        let mut memory_set = Self::new_bare();
+        let mut memory_set = Self::with_asid(AsidHandle::kernel()).unwrap();
         // map trampoline
-        memory_set.map_trampoline();
+        memory_set.map_trampoline().unwrap();
         // map kernel sections
         info!(".text [{:#x}, {:#x})", stext as usize, etext as usize);
         info!(".rodata [{:#x}, {:#x})", srodata as usize, erodata as usize);
@@ -128,7 +157,7 @@ impl MemorySet {
                 MapPermission::R | MapPermission::X,
             ),
             None,
-        );
+        ).unwrap();
         info!("mapping .rodata section");
         memory_set.push(
             MapArea::new(
@@ -138,7 +167,7 @@ impl MemorySet {
                 MapPermission::R,
             ),
             None,
-        );
+        ).unwrap();
         info!("mapping .data section");
         memory_set.push(
             MapArea::new(
@@ -148,7 +177,7 @@ impl MemorySet {
                 MapPermission::R | MapPermission::W,
             ),
             None,
-        );
+        ).unwrap();
         info!("mapping .bss section");
         memory_set.push(
             MapArea::new(
@@ -158,25 +187,38 @@ impl MemorySet {
                 MapPermission::R | MapPermission::W,
             ),
             None,
-        );
+        ).unwrap();
         info!("mapping physical memory");
         memory_set.push(
-            MapArea::new(
+            MapArea::new_huge(
                 (ekernel as usize).into(),
                 MEMORY_END.into(),
                 MapType::Identical,
                 MapPermission::R | MapPermission::W,
             ),
             None,
-        );
+        ).unwrap();
+        info!("mapping memory-mapped registers");
+        for pair in MMIO {
+            memory_set.push(
+                MapArea::new(
+                    pair.0.into(),
+                    (pair.0 + pair.1).into(),
+                    MapType::Identical,
+                    MapPermission::R | MapPermission::W,
+                ),
+                None,
+            ).unwrap();
+        }
         memory_set
     }
-    /// Include sections in elf and trampoline and TrapContext and user stack,
-    /// also returns user_sp and entry point.
-    pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
-        let mut memory_set = Self::new_bare();
+    /// Include sections in elf and trampoline and TrapContext and user stack
+    /// of `user_stack_size` bytes, also returns user_sp and entry point.
+    pub fn from_elf(elf_data: &[u8], user_stack_size: usize) -> Option<(Self, usize, usize)> {
+        let mut memory_set = Self::new_bare()?;
         // map trampoline
-        memory_set.map_trampoline();
+        memory_set.map_trampoline()?;
+        memory_set.map_vdso()?;
         // map program headers of elf, with U flag
         let elf = xmas_elf::ElfFile::new(elf_data).unwrap();
         let elf_header = elf.header;
@@ -205,7 +247,7 @@ impl MemorySet {
                 memory_set.push(
                     map_area,
                     Some(&elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]),
-                );
+                )?;
             }
         }
         // map user stack with U flags
@@ -213,7 +255,7 @@ impl MemorySet {
         let mut user_stack_bottom: usize = max_end_va.into();
         // guard page
         user_stack_bottom += PAGE_SIZE;
-        let user_stack_top = user_stack_bottom + USER_STACK_SIZE;
+        let user_stack_top = user_stack_bottom + user_stack_size;
         memory_set.push(
             MapArea::new(
                 user_stack_bottom.into(),
@@ -222,7 +264,7 @@ impl MemorySet {
                 MapPermission::R | MapPermission::W | MapPermission::U,
             ),
             None,
-        );
+        )?;
         // map TrapContext
         memory_set.push(
             MapArea::new(
@@ -232,19 +274,19 @@ impl MemorySet {
                 MapPermission::R | MapPermission::W,
             ),
             None,
-        );
-        (
+        )?;
+        Some((
             memory_set,
             user_stack_top,
             elf.header.pt2.entry_point() as usize,
-        )
+        ))
     }
     pub fn activate(&self) {
         let satp = self.page_table.token();
         unsafe {
             satp::write(satp);
-            core::arch::asm!("sfence.vma");
         }
+        sfence_vma_asid(self.page_table.asid());
     }
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
         self.page_table.translate(vpn)
@@ -258,18 +300,56 @@ impl MemorySet {
         self.areas.iter().any(|area| area.includes(vr))
     }
 
+    /// Whether `vpn` is in one of the areas, whose frames belong to this
+    /// address space alone, unlike the time page shared by all.
+    pub fn is_private(&self, vpn: VirtPageNum) -> bool {
+        self.areas.iter().any(|area| {
+            area.map_type == MapType::Framed
+                && area.vpn_range.get_start() <= vpn
+                && vpn < area.vpn_range.get_end()
+        })
+    }
+
+    /// Number of frames backing the areas, page table frames excluded.
+    pub fn resident_pages(&self) -> usize {
+        self.areas.iter().map(|area| area.resident_pages()).sum()
+    }
+
+    /// Number of pages covered by the areas, whether backed by frames or not.
+    pub fn mapped_pages(&self) -> usize {
+        self.areas
+            .iter()
+            .map(|area| area.vpn_range.get_end().0 - area.vpn_range.get_start().0)
+            .sum()
+    }
+
+    /// Describe every area in this memory set, in insertion order.
+    pub fn areas_info(&self) -> Vec<MapAreaInfo> {
+        self.areas.iter().map(|area| area.info()).collect()
+    }
 
-    pub fn mmap(&mut self, start: VirtAddr, len: usize, perm: MapPermission) -> isize {
+
+    pub fn mmap(
+        &mut self,
+        start: VirtAddr,
+        len: usize,
+        perm: MapPermission,
+        huge: bool,
+    ) -> Result<(), MapError> {
         let end = VirtAddr(start.0 + len);
         let vr = VPNRange::new(start.floor(), end.ceil());
         if self.includes(vr) {
-            return -1;
+            return Err(MapError::Overlap);
         }
-        self.push(MapArea::new(start, end, MapType::Framed, perm), None);
-        0
+        let area = if huge {
+            MapArea::new_huge(start, end, MapType::Framed, perm)
+        } else {
+            MapArea::new(start, end, MapType::Framed, perm)
+        };
+        self.push(area, None).ok_or(MapError::NoMemory)
     }
 
-    pub fn munmap(&mut self, start: VirtAddr, len: usize) -> isize {
+    pub fn munmap(&mut self, start: VirtAddr, len: usize) -> Result<(), MapError> {
         let end = VirtAddr(start.0 + len);
         let vr = VPNRange::new(start.floor(), end.ceil());
         let pos = self.areas.iter().position(|area| area.match_range(vr));
@@ -278,19 +358,45 @@ impl MemorySet {
             Some(idx) => {
                 self.areas[idx].unmap(&mut self.page_table);
                 self.areas.remove(idx);
-                0
+                Ok(())
             }
-            None => -1,
+            None => Err(MapError::NotMapped),
+        }
+    }
+
+    /// Let the pages from `start` on fault as if their memory were broken:
+    /// their frames are freed and they point at [`POISON_PA`], which neither
+    /// the process nor the kernel can access. They must lie in one user area
+    /// of 4 KiB pages.
+    pub fn poison(&mut self, start: VirtAddr, len: usize) -> Result<(), MapError> {
+        let end = VirtAddr(start.0 + len);
+        let vr = VPNRange::new(start.floor(), end.ceil());
+        let area = self
+            .areas
+            .iter_mut()
+            .find(|area| {
+                area.includes(vr)
+                    && area.map_type == MapType::Framed
+                    && area.map_perm.contains(MapPermission::U)
+                    && !area.huge
+            })
+            .ok_or(MapError::NotMapped)?;
+        for vpn in vr {
+            area.poison_one(&mut self.page_table, vpn);
         }
+        Ok(())
     }
 }
 
 /// map area structure, controls a contiguous piece of virtual memory
 pub struct MapArea {
     vpn_range: VPNRange,
+    /// a megapage frame is keyed by its first vpn
     data_frames: BTreeMap<VirtPageNum, FrameTracker>,
     map_type: MapType,
     map_perm: MapPermission,
+    /// map the megapage aligned part with 2 MiB pages
+    huge: bool,
 }
 
 impl MapArea {
@@ -307,22 +413,85 @@ impl MapArea {
             data_frames: BTreeMap::new(),
             map_type,
             map_perm,
+            huge: false,
         }
     }
-    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+    /// Like [`MapArea::new`], but use megapages wherever a whole aligned 2 MiB
+    /// fits in the area.
+    pub fn new_huge(
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        map_type: MapType,
+        map_perm: MapPermission,
+    ) -> Self {
+        let mut area = Self::new(start_va, end_va, map_type, map_perm);
+        area.huge = true;
+        area
+    }
+    /// Whether a megapage could start at `vpn`.
+    fn huge_at(&self, vpn: VirtPageNum) -> bool {
+        self.huge
+            && vpn.0 % HUGE_PAGE_PAGES == 0
+            && vpn.0 + HUGE_PAGE_PAGES <= self.vpn_range.get_end().0
+    }
+    /// Map a megapage at `vpn`. Return false if no contiguous frames are left,
+    /// in which case nothing is mapped.
+    fn map_huge_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> bool {
+        let ppn: PhysPageNum;
+        match self.map_type {
+            MapType::Identical => {
+                ppn = PhysPageNum(vpn.0);
+            }
+            MapType::Framed => {
+                let frame = match frame_alloc_contig(HUGE_PAGE_ORDER) {
+                    Some(frame) => frame,
+                    None => return false,
+                };
+                ppn = frame.ppn;
+                self.data_frames.insert(vpn, frame);
+            }
+        }
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+        if page_table.map_huge(vpn, ppn, pte_flags).is_none() {
+            self.data_frames.remove(&vpn);
+            return false;
+        }
+        true
+    }
+    /// Whether `vpn` starts a megapage mapped by this area.
+    fn is_huge_mapped(&self, vpn: VirtPageNum) -> bool {
+        self.huge_at(vpn)
+            && match self.map_type {
+                MapType::Identical => true,
+                MapType::Framed => self
+                    .data_frames
+                    .get(&vpn)
+                    .map_or(false, |frame| frame.order == HUGE_PAGE_ORDER),
+            }
+    }
+    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> Option<()> {
         let ppn: PhysPageNum;
         match self.map_type {
             MapType::Identical => {
                 ppn = PhysPageNum(vpn.0);
             }
             MapType::Framed => {
-                let frame = frame_alloc().unwrap();
+                let frame = frame_alloc()?;
                 ppn = frame.ppn;
                 self.data_frames.insert(vpn, frame);
             }
         }
         let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
-        page_table.map(vpn, ppn, pte_flags);
+        if page_table.map(vpn, ppn, pte_flags).is_none() {
+            self.data_frames.remove(&vpn);
+            return None;
+        }
+        Some(())
+    }
+    /// Point `vpn` at [`POISON_PA`] and free its frame.
+    fn poison_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        page_table.remap(vpn, PhysAddr::from(POISON_PA).into());
+        self.data_frames.remove(&vpn);
     }
     #[allow(unused)]
     pub fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
@@ -335,15 +504,38 @@ impl MapArea {
         }
         page_table.unmap(vpn);
     }
-    pub fn map(&mut self, page_table: &mut PageTable) {
-        for vpn in self.vpn_range {
-            self.map_one(page_table, vpn);
+    /// On running out of frames, undo the pages mapped so far.
+    pub fn map(&mut self, page_table: &mut PageTable) -> Option<()> {
+        let mut vpn = self.vpn_range.get_start();
+        while vpn < self.vpn_range.get_end() {
+            // fall back to 4 KiB pages if contiguous frames run out
+            if self.huge_at(vpn) && self.map_huge_one(page_table, vpn) {
+                vpn.0 += HUGE_PAGE_PAGES;
+            } else if self.map_one(page_table, vpn).is_some() {
+                vpn.step();
+            } else {
+                self.unmap_until(page_table, vpn);
+                return None;
+            }
         }
+        Some(())
     }
     #[allow(unused)]
     pub fn unmap(&mut self, page_table: &mut PageTable) {
-        for vpn in self.vpn_range {
-            self.unmap_one(page_table, vpn);
+        self.unmap_until(page_table, self.vpn_range.get_end());
+    }
+    /// unmap the pages of the area below `end`
+    fn unmap_until(&mut self, page_table: &mut PageTable, end: VirtPageNum) {
+        let mut vpn = self.vpn_range.get_start();
+        while vpn < end {
+            if self.is_huge_mapped(vpn) {
+                self.data_frames.remove(&vpn);
+                page_table.unmap_huge(vpn);
+                vpn.0 += HUGE_PAGE_PAGES;
+            } else {
+                self.unmap_one(page_table, vpn);
+                vpn.step();
+            }
         }
     }
 
@@ -377,6 +569,7 @@ impl MapArea {
             data_frames: BTreeMap::new(),
             map_type: another.map_type,
             map_perm: another.map_perm,
+            huge: another.huge,
         }
     }
 
@@ -387,13 +580,54 @@ impl MapArea {
     pub fn match_range(&self, vr: VPNRange) -> bool {
         self.vpn_range.get_start() == vr.get_start() && self.vpn_range.get_end() == vr.get_end()
     }
+
+    /// number of frames held in `data_frames`
+    pub fn resident_pages(&self) -> usize {
+        self.data_frames.values().map(|frame| frame.pages()).sum()
+    }
+
+    pub fn info(&self) -> MapAreaInfo {
+        MapAreaInfo {
+            start_vpn: self.vpn_range.get_start().0,
+            end_vpn: self.vpn_range.get_end().0,
+            map_type: self.map_type as usize,
+            map_perm: self.map_perm.bits as usize,
+            resident: self.resident_pages(),
+            huge: self.huge as usize,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+/// layout of a [`MapArea`] as reported to user space
+pub struct MapAreaInfo {
+    pub start_vpn: usize,
+    pub end_vpn: usize,
+    pub map_type: usize,
+    pub map_perm: usize,
+    /// number of frames held in `data_frames`
+    pub resident: usize,
+    /// 1 if the area may be mapped with megapages
+    pub huge: usize,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+/// why an mmap or munmap request on a [`MemorySet`] failed
+pub enum MapError {
+    /// the range overlaps an existing area
+    Overlap,
+    /// no area covers exactly the range
+    NotMapped,
+    /// frames ran out
+    NoMemory,
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 /// map type for memory set: identical or framed
 pub enum MapType {
-    Identical,
-    Framed,
+    Identical = 0,
+    Framed = 1,
 }
 
 bitflags! {
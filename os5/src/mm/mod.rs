@@ -8,22 +8,27 @@
 
 
 mod address;
+mod asid;
 mod frame_allocator;
 mod heap_allocator;
 mod memory_set;
 mod page_table;
+mod user_ptr;
 
 pub use address::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
 use address::{StepByOne, VPNRange};
-pub use frame_allocator::{frame_alloc, FrameTracker};
+pub use frame_allocator::{frame_alloc, frame_alloc_contig, frame_stats, FrameTracker};
+pub use heap_allocator::heap_stats;
 pub use memory_set::remap_test;
-pub use memory_set::{MapPermission, MemorySet, KERNEL_SPACE};
-pub use page_table::{translated_byte_buffer, PageTableEntry};
-pub use page_table::{PTEFlags, PageTable, translated_str, translated_refmut};
+pub use memory_set::{MapAreaInfo, MapError, MapPermission, MemorySet, KERNEL_SPACE};
+pub use page_table::{PTEFlags, PageTable, PageTableEntry};
+pub use user_ptr::{UserFault, UserPtr, UserSlice};
 
 /// initiate heap allocator, frame allocator and kernel space
 pub fn init() {
     heap_allocator::init_heap();
     frame_allocator::init_frame_allocator();
+    frame_allocator::frame_allocator_contig_test();
     KERNEL_SPACE.exclusive_access().activate();
+    asid::init_asid();
 }
@@ -1,8 +1,9 @@
 //! Implementation of [`PageTableEntry`] and [`PageTable`].
 
-use super::{frame_alloc, FrameTracker, PhysPageNum, StepByOne, VirtAddr, VirtPageNum, PhysAddr};
+use super::{frame_alloc, FrameTracker, PhysPageNum, VirtAddr, VirtPageNum, PhysAddr};
+use super::asid::{make_token, sfence_vma, token_asid};
+use crate::config::HUGE_PAGE_PAGES;
 use alloc::vec;
-use alloc::string::String;
 use alloc::vec::Vec;
 use bitflags::*;
 
@@ -54,42 +55,57 @@ impl PageTableEntry {
     pub fn executable(&self) -> bool {
         (self.flags() & PTEFlags::X) != PTEFlags::empty()
     }
+    /// A valid entry with any of `R W X` set maps memory instead of pointing
+    /// to the next level page table.
+    pub fn is_leaf(&self) -> bool {
+        self.is_valid() && (self.flags() & (PTEFlags::R | PTEFlags::W | PTEFlags::X)) != PTEFlags::empty()
+    }
 }
 
 /// page table structure
 pub struct PageTable {
     root_ppn: PhysPageNum,
     frames: Vec<FrameTracker>,
+    /// tags the TLB entries of this address space
+    asid: usize,
 }
 
-/// Assume that it won't oom when creating/mapping.
+/// Creating and mapping return `None` when frames run out.
 impl PageTable {
-    pub fn new() -> Self {
-        let frame = frame_alloc().unwrap();
-        PageTable {
+    pub fn new(asid: usize) -> Option<Self> {
+        let frame = frame_alloc()?;
+        Some(PageTable {
             root_ppn: frame.ppn,
             frames: vec![frame],
-        }
+            asid,
+        })
     }
     /// Temporarily used to get arguments from user space.
     pub fn from_token(satp: usize) -> Self {
         Self {
             root_ppn: PhysPageNum::from(satp & ((1usize << 44) - 1)),
             frames: Vec::new(),
+            asid: token_asid(satp),
         }
     }
     fn find_pte_create(&mut self, vpn: VirtPageNum) -> Option<&mut PageTableEntry> {
+        self.find_pte_create_at(vpn, 2)
+    }
+    /// Walk down to the entry of `vpn` at `level`, 2 for a 4 KiB page and
+    /// 1 for a 2 MiB megapage, creating the page tables in between.
+    fn find_pte_create_at(&mut self, vpn: VirtPageNum, level: usize) -> Option<&mut PageTableEntry> {
         let mut idxs = vpn.indexes();
         let mut ppn = self.root_ppn;
         let mut result: Option<&mut PageTableEntry> = None;
         for (i, idx) in idxs.iter_mut().enumerate() {
             let pte = &mut ppn.get_pte_array()[*idx];
-            if i == 2 {
+            if i == level {
                 result = Some(pte);
                 break;
             }
+            assert!(!pte.is_leaf(), "vpn {:?} is covered by a megapage", vpn);
             if !pte.is_valid() {
-                let frame = frame_alloc().unwrap();
+                let frame = frame_alloc()?;
                 *pte = PageTableEntry::new(frame.ppn, PTEFlags::V);
                 self.frames.push(frame);
             }
@@ -97,14 +113,16 @@ impl PageTable {
         }
         result
     }
-    fn find_pte(&self, vpn: VirtPageNum) -> Option<&PageTableEntry> {
+    /// Find the entry mapping `vpn`, which is a megapage entry if the walk
+    /// meets a leaf at level 1. Also return the level it was found at.
+    fn find_pte(&self, vpn: VirtPageNum) -> Option<(&PageTableEntry, usize)> {
         let idxs = vpn.indexes();
         let mut ppn = self.root_ppn;
-        let mut result: Option<&PageTableEntry> = None;
+        let mut result: Option<(&PageTableEntry, usize)> = None;
         for (i, idx) in idxs.iter().enumerate() {
             let pte = &ppn.get_pte_array()[*idx];
-            if i == 2 {
-                result = Some(pte);
+            if i == 2 || pte.is_leaf() {
+                result = Some((pte, i));
                 break;
             }
             if !pte.is_valid() {
@@ -115,22 +133,60 @@ impl PageTable {
         result
     }
     #[allow(unused)]
-    pub fn map(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
-        let pte = self.find_pte_create(vpn).unwrap();
+    pub fn map(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) -> Option<()> {
+        let pte = self.find_pte_create(vpn)?;
         assert!(!pte.is_valid(), "vpn {:?} is mapped before mapping", vpn);
         *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+        Some(())
     }
     #[allow(unused)]
     pub fn unmap(&mut self, vpn: VirtPageNum) {
         let pte = self.find_pte_create(vpn).unwrap();
         assert!(pte.is_valid(), "vpn {:?} is invalid before unmapping", vpn);
         *pte = PageTableEntry::empty();
+        sfence_vma(VirtAddr::from(vpn).0, self.asid);
     }
+    /// Point the 4 KiB page `vpn` at `ppn` instead, with the same flags.
+    pub fn remap(&mut self, vpn: VirtPageNum, ppn: PhysPageNum) {
+        let pte = self.find_pte_create(vpn).unwrap();
+        assert!(pte.is_valid(), "vpn {:?} is invalid before remapping", vpn);
+        *pte = PageTableEntry::new(ppn, pte.flags());
+        sfence_vma(VirtAddr::from(vpn).0, self.asid);
+    }
+    /// Map the 2 MiB megapage starting at `vpn` to the frames starting at `ppn`,
+    /// both of which must be aligned to 512 pages.
+    pub fn map_huge(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) -> Option<()> {
+        assert_eq!(vpn.0 % HUGE_PAGE_PAGES, 0, "{:?} is not megapage aligned", vpn);
+        assert_eq!(ppn.0 % HUGE_PAGE_PAGES, 0, "{:?} is not megapage aligned", ppn);
+        let pte = self.find_pte_create_at(vpn, 1)?;
+        assert!(!pte.is_valid(), "vpn {:?} is mapped before mapping", vpn);
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+        Some(())
+    }
+    pub fn unmap_huge(&mut self, vpn: VirtPageNum) {
+        let pte = self.find_pte_create_at(vpn, 1).unwrap();
+        assert!(pte.is_leaf(), "vpn {:?} is not a megapage before unmapping", vpn);
+        *pte = PageTableEntry::empty();
+        // one fence covers the whole megapage
+        sfence_vma(VirtAddr::from(vpn).0, self.asid);
+    }
+    /// For a page inside a megapage, the returned entry carries the ppn of
+    /// that very page rather than the start of the megapage.
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
-        self.find_pte(vpn).copied()
+        self.find_pte(vpn).map(|(pte, level)| {
+            if level == 1 {
+                PageTableEntry::new(
+                    PhysPageNum(pte.ppn().0 + vpn.0 % HUGE_PAGE_PAGES),
+                    pte.flags(),
+                )
+            } else {
+                *pte
+            }
+        })
     }
+    #[allow(unused)]
     pub fn translate_va(&self, va: VirtAddr) -> Option<PhysAddr> {
-        self.find_pte(va.clone().floor()).map(|pte| {
+        self.translate(va.clone().floor()).map(|pte| {
             //println!("translate_va:va = {:?}", va);
             let aligned_pa: PhysAddr = pte.ppn().into();
             //println!("translate_va:pa_align = {:?}", aligned_pa);
@@ -140,58 +196,9 @@ impl PageTable {
         })
     }
     pub fn token(&self) -> usize {
-        8usize << 60 | self.root_ppn.0
+        make_token(self.asid, self.root_ppn.0)
     }
-}
-
-/// translate a pointer to a mutable u8 Vec through page table
-pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&'static mut [u8]> {
-    let page_table = PageTable::from_token(token);
-    let mut start = ptr as usize;
-    let end = start + len;
-    let mut v = Vec::new();
-    while start < end {
-        let start_va = VirtAddr::from(start);
-        let mut vpn = start_va.floor();
-        let ppn = page_table.translate(vpn).unwrap().ppn();
-        vpn.step();
-        let mut end_va: VirtAddr = vpn.into();
-        end_va = end_va.min(VirtAddr::from(end));
-        if end_va.page_offset() == 0 {
-            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..]);
-        } else {
-            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..end_va.page_offset()]);
-        }
-        start = end_va.into();
+    pub fn asid(&self) -> usize {
+        self.asid
     }
-    v
-}
-
-pub fn translated_str(token: usize, ptr: *const u8) -> String {
-    let page_table = PageTable::from_token(token);
-    let mut string = String::new();
-    let mut va = ptr as usize;
-    loop{
-        let ch: u8 = *(page_table.translate_va(VirtAddr::from(va)).unwrap().get_mut());
-        if ch == 0 {
-            break;
-        }
-        else {
-            string.push(ch as char);
-            va += 1;
-        }
-    }
-    string
-}
-
-
-pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> &'static mut T {
-    //println!("into translated_refmut!");
-    let page_table = PageTable::from_token(token);
-    let va = ptr as usize;
-    //println!("translated_refmut: before translate_va");
-    page_table
-        .translate_va(VirtAddr::from(va))
-        .unwrap()
-        .get_mut()
 }
@@ -0,0 +1,220 @@
+//! Checked access to user memory through [`UserPtr`] and [`UserSlice`].
+//!
+//! Syscalls must not trust the pointers handed over by user space. Every page
+//! touched is looked up in the address space given by `token` and has to be
+//! mapped with the `U` flag and the permission the access needs, otherwise
+//! the access fails with [`UserFault`] instead of panicking the kernel.
+//! Copies may straddle page boundaries, and one that faults all the same
+//! fails too, see [`crate::trap::copy_from_nofault`].
+
+use super::{PTEFlags, PageTable, PhysAddr, PhysPageNum, StepByOne, VirtAddr};
+use crate::config::PAGE_SIZE;
+use crate::trap::{copy_from_nofault, copy_to_nofault};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::mem::{size_of, MaybeUninit};
+
+/// Sv39 addresses must sign extend bit 38, the ones between the lower and
+/// the upper half map nothing. Pages of the upper half may still be off
+/// limits, without the `U` flag.
+const LOWER_HALF_END: usize = 1 << 38;
+const UPPER_HALF_START: usize = LOWER_HALF_END.wrapping_neg();
+
+/// the longest string [`UserPtr::read_str`] reads, nul included
+const MAX_STR_LEN: usize = 4096;
+
+/// Whether the bytes from `start` on to `end`, excluded, are all canonical.
+fn is_canonical(start: usize, end: usize) -> bool {
+    end <= LOWER_HALF_END || start >= UPPER_HALF_START
+}
+
+/// a user address is unmapped or lacks the permission for the access
+#[derive(Copy, Clone, Debug)]
+pub struct UserFault;
+
+/// Translate the page of `va`, which must be accessible from U-mode with `flags`.
+fn translate_user(
+    page_table: &PageTable,
+    va: VirtAddr,
+    flags: PTEFlags,
+) -> Result<PhysPageNum, UserFault> {
+    let pte = page_table.translate(va.floor()).ok_or(UserFault)?;
+    if pte.is_valid() && pte.flags().contains(flags | PTEFlags::U) {
+        Ok(pte.ppn())
+    } else {
+        Err(UserFault)
+    }
+}
+
+/// `len` bytes of user memory starting at `ptr`
+#[derive(Copy, Clone)]
+pub struct UserSlice {
+    token: usize,
+    ptr: usize,
+    len: usize,
+}
+
+impl UserSlice {
+    pub fn new(token: usize, ptr: *const u8, len: usize) -> Self {
+        Self {
+            token,
+            ptr: ptr as usize,
+            len,
+        }
+    }
+    /// Split the slice into pieces of physical memory, one per page, after
+    /// checking that each page allows `flags`.
+    fn buffers(&self, flags: PTEFlags) -> Result<Vec<&'static mut [u8]>, UserFault> {
+        let end = self.ptr.checked_add(self.len).ok_or(UserFault)?;
+        if !is_canonical(self.ptr, end) {
+            return Err(UserFault);
+        }
+        let page_table = PageTable::from_token(self.token);
+        let mut start = self.ptr;
+        let mut v = Vec::new();
+        while start < end {
+            let start_va = VirtAddr::from(start);
+            let ppn = translate_user(&page_table, start_va, flags)?;
+            let mut vpn = start_va.floor();
+            vpn.step();
+            let mut end_va: VirtAddr = vpn.into();
+            end_va = end_va.min(VirtAddr::from(end));
+            if end_va.page_offset() == 0 {
+                v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..]);
+            } else {
+                v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..end_va.page_offset()]);
+            }
+            start = end_va.into();
+        }
+        Ok(v)
+    }
+    /// pieces of the slice which may be read
+    pub fn readable_buffers(&self) -> Result<Vec<&'static mut [u8]>, UserFault> {
+        self.buffers(PTEFlags::R)
+    }
+    /// pieces of the slice which may be written
+    pub fn writable_buffers(&self) -> Result<Vec<&'static mut [u8]>, UserFault> {
+        self.buffers(PTEFlags::W)
+    }
+    /// Copy the slice into `dst`, whose length must match.
+    pub fn read_into(&self, dst: &mut [u8]) -> Result<(), UserFault> {
+        assert_eq!(dst.len(), self.len);
+        let mut start = 0;
+        for buffer in self.readable_buffers()? {
+            let end = start + buffer.len();
+            if !copy_from_nofault(&mut dst[start..end], buffer.as_ptr() as usize) {
+                return Err(UserFault);
+            }
+            start = end;
+        }
+        Ok(())
+    }
+    /// Copy `src`, whose length must match, into the slice. Nothing is
+    /// written if any page of the slice is not writable.
+    pub fn write_from(&self, src: &[u8]) -> Result<(), UserFault> {
+        assert_eq!(src.len(), self.len);
+        let mut start = 0;
+        for buffer in self.writable_buffers()? {
+            let end = start + buffer.len();
+            if !copy_to_nofault(buffer.as_mut_ptr() as usize, &src[start..end]) {
+                return Err(UserFault);
+            }
+            start = end;
+        }
+        Ok(())
+    }
+}
+
+/// a typed pointer into user memory
+pub struct UserPtr<T> {
+    token: usize,
+    ptr: usize,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> Clone for UserPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for UserPtr<T> {}
+
+impl<T> UserPtr<T> {
+    pub fn new(token: usize, ptr: *const T) -> Self {
+        Self {
+            token,
+            ptr: ptr as usize,
+            _marker: PhantomData,
+        }
+    }
+    pub fn is_null(&self) -> bool {
+        self.ptr == 0
+    }
+    /// the `count`-th element of an array starting at this pointer
+    pub fn add(&self, count: usize) -> Self {
+        Self {
+            token: self.token,
+            ptr: self.ptr.wrapping_add(count.wrapping_mul(size_of::<T>())),
+            _marker: PhantomData,
+        }
+    }
+    fn as_slice(&self) -> UserSlice {
+        UserSlice::new(self.token, self.ptr as *const u8, size_of::<T>())
+    }
+    /// Write `value` byte by byte, so the pointer needs no alignment.
+    pub fn write(&self, value: &T) -> Result<(), UserFault> {
+        let src =
+            unsafe { core::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) };
+        self.as_slice().write_from(src)
+    }
+}
+
+impl<T: Copy> UserPtr<T> {
+    pub fn read(&self) -> Result<T, UserFault> {
+        let mut value = MaybeUninit::<T>::uninit();
+        let dst = unsafe {
+            core::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>())
+        };
+        self.as_slice().read_into(dst)?;
+        Ok(unsafe { value.assume_init() })
+    }
+}
+
+impl UserPtr<u8> {
+    /// Read a nul-terminated string, scanning a page at a time. A string
+    /// longer than [`MAX_STR_LEN`] fails too, so the kernel heap is safe.
+    pub fn read_str(&self) -> Result<String, UserFault> {
+        let page_table = PageTable::from_token(self.token);
+        let mut bytes = Vec::new();
+        let mut va = self.ptr;
+        loop {
+            if !is_canonical(va, va.saturating_add(1)) {
+                return Err(UserFault);
+            }
+            let start_va = VirtAddr::from(va);
+            let ppn = translate_user(&page_table, start_va, PTEFlags::R)?;
+            let len = (PAGE_SIZE - start_va.page_offset()).min(MAX_STR_LEN - bytes.len());
+            let mut page = [0u8; PAGE_SIZE];
+            let page = &mut page[..len];
+            let src = PhysAddr::from(ppn).0 + start_va.page_offset();
+            if !copy_from_nofault(page, src) {
+                return Err(UserFault);
+            }
+            match page.iter().position(|&ch| ch == 0) {
+                Some(len) => {
+                    bytes.extend_from_slice(&page[..len]);
+                    break;
+                }
+                None if bytes.len() + page.len() == MAX_STR_LEN => return Err(UserFault),
+                None => {
+                    bytes.extend_from_slice(page);
+                    va = va.checked_add(page.len()).ok_or(UserFault)?;
+                }
+            }
+        }
+        // app names and paths are ASCII, take each byte as a char
+        Ok(bytes.iter().map(|&ch| ch as char).collect())
+    }
+}
@@ -0,0 +1,88 @@
+//! Error numbers of failed syscalls
+//!
+//! A syscall that fails returns the negated error number in `a0`, the
+//! numbers being the same as Linux. Only programs which ask for it get the
+//! numbers, the others see the errors of the labs, see [`ErrorAbi`].
+
+use super::SYSCALL_WAITPID;
+use crate::mm::{MapError, UserFault};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(isize)]
+pub enum Errno {
+    /// operation not permitted
+    EPERM = 1,
+    /// no such file or directory
+    ENOENT = 2,
+    /// no such process
+    ESRCH = 3,
+    /// bad file descriptor
+    EBADF = 9,
+    /// no child processes
+    ECHILD = 10,
+    /// try again
+    EAGAIN = 11,
+    /// out of memory
+    ENOMEM = 12,
+    /// bad address
+    EFAULT = 14,
+    /// already exists
+    EEXIST = 17,
+    /// invalid argument
+    EINVAL = 22,
+    /// invalid syscall number
+    ENOSYS = 38,
+}
+
+/// what a `sys_` function returns
+pub type SyscallResult = Result<usize, Errno>;
+
+impl From<UserFault> for Errno {
+    fn from(_: UserFault) -> Self {
+        Errno::EFAULT
+    }
+}
+
+impl From<MapError> for Errno {
+    fn from(err: MapError) -> Self {
+        match err {
+            MapError::Overlap => Errno::EEXIST,
+            MapError::NotMapped => Errno::EINVAL,
+            MapError::NoMemory => Errno::ENOMEM,
+        }
+    }
+}
+
+/// the section a program has if it wants error numbers, put there by the
+/// user library of this repo
+const ERRNO_SECTION: &str = ".rcore_errno";
+
+/// how a program is told that a syscall failed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorAbi {
+    /// the negated error number
+    Errno,
+    /// -1, or -2 from `waitpid` for a child which is still running, which is
+    /// what the programs of the labs and their graders check
+    Legacy,
+}
+
+impl ErrorAbi {
+    /// The ABI the program in `elf_data` is built for.
+    pub fn of(elf_data: &[u8]) -> Self {
+        match xmas_elf::ElfFile::new(elf_data) {
+            Ok(elf) if elf.find_section_by_name(ERRNO_SECTION).is_some() => ErrorAbi::Errno,
+            _ => ErrorAbi::Legacy,
+        }
+    }
+}
+
+/// Encode the `result` of syscall `syscall_id` for `a0`.
+pub fn encode(result: SyscallResult, syscall_id: usize, abi: ErrorAbi) -> isize {
+    match (result, abi) {
+        (Ok(value), _) => value as isize,
+        (Err(errno), ErrorAbi::Errno) => -(errno as isize),
+        (Err(Errno::EAGAIN), ErrorAbi::Legacy) if syscall_id == SYSCALL_WAITPID => -2,
+        (Err(_), ErrorAbi::Legacy) => -1,
+    }
+}
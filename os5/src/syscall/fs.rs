@@ -1,17 +1,23 @@
 //! File and filesystem-related syscalls
 
-use crate::mm::translated_byte_buffer;
+use super::{Errno, SyscallResult};
+use crate::config::PAGE_SIZE;
+use crate::mm::UserSlice;
 use crate::task::{current_user_token, suspend_current_and_run_next};
 use crate::sbi::console_getchar;
+use crate::trap::copy_from_nofault;
+use alloc::string::String;
 
 const FD_STDIN: usize = 0;
 const FD_STDOUT: usize = 1;
 
-pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
+pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> SyscallResult {
     match fd {
         FD_STDIN => {
-            assert_eq!(len, 1, "Only support len = 1 in sys_read!");
-
+            // Only support len = 1 in sys_read
+            if len != 1 {
+                return Err(Errno::EINVAL);
+            }
             let mut c: usize;
             loop{
                 c = console_getchar();
@@ -23,27 +29,28 @@ pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
                 }
             }
             let ch = c as u8;
-            let mut buffers = translated_byte_buffer(current_user_token(), buf, len);
-            unsafe {buffers[0].as_mut_ptr().write_volatile(ch); }
-            1
-        }
-        _ => {
-            panic!("Unsupported fd in sys_read!");
+            UserSlice::new(current_user_token(), buf, len).write_from(&[ch])?;
+            Ok(1)
         }
+        _ => Err(Errno::EBADF),
     }
 }
 
-pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
+pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> SyscallResult {
     match fd {
         FD_STDOUT => {
-            let buffers = translated_byte_buffer(current_user_token(), buf, len);
+            // print a page at a time, as `len` is up to the user
+            let buffers = UserSlice::new(current_user_token(), buf, len).readable_buffers()?;
+            let mut page = [0u8; PAGE_SIZE];
             for buffer in buffers {
-                print!("{}", core::str::from_utf8(buffer).unwrap());
+                let page = &mut page[..buffer.len()];
+                if !copy_from_nofault(page, buffer.as_ptr() as usize) {
+                    return Err(Errno::EFAULT);
+                }
+                print!("{}", String::from_utf8_lossy(page));
             }
-            len as isize
-        }
-        _ => {
-            panic!("Unsupported fd in sys_write!");
+            Ok(len)
         }
+        _ => Err(Errno::EBADF),
     }
 }
@@ -13,42 +13,123 @@
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
+const SYSCALL_PTRACE: usize = 117;
 const SYSCALL_YIELD: usize = 124;
 const SYSCALL_GET_TIME: usize = 169;
 const SYSCALL_GETPID: usize = 172;
+const SYSCALL_GETPPID: usize = 173;
 const SYSCALL_FORK: usize = 220;
 const SYSCALL_EXEC: usize = 221;
 const SYSCALL_WAITPID: usize = 260;
 const SYSCALL_SPAWN: usize = 400;
 const SYSCALL_MUNMAP: usize = 215;
 const SYSCALL_MMAP: usize = 222;
+const SYSCALL_MADVISE: usize = 233;
 const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_TIMES: usize = 153;
+const SYSCALL_SETPGID: usize = 154;
+const SYSCALL_GETPGID: usize = 155;
+const SYSCALL_GETSID: usize = 156;
+const SYSCALL_SETSID: usize = 157;
+const SYSCALL_GETRLIMIT: usize = 163;
+const SYSCALL_SETRLIMIT: usize = 164;
+const SYSCALL_GETRUSAGE: usize = 165;
+const SYSCALL_PRCTL: usize = 167;
 const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_TASK_MAPS: usize = 411;
+const SYSCALL_MEMINFO: usize = 412;
+const SYSCALL_TRACE: usize = 413;
+const SYSCALL_TRACE_READ: usize = 414;
+const SYSCALL_SECCOMP: usize = 415;
+const SYSCALL_PROC_LIST: usize = 416;
+const SYSCALL_PROC_INFO: usize = 417;
+const SYSCALL_COREDUMP: usize = 418;
+const SYSCALL_RING_SETUP: usize = 425;
+const SYSCALL_RING_ENTER: usize = 426;
 
+mod errno;
 mod fs;
 mod process;
+mod ptrace;
+mod ring;
 
+pub use errno::{ErrorAbi, Errno, SyscallResult};
 use fs::*;
 use process::*;
-pub use process::TaskInfo;
+use ptrace::*;
+use ring::*;
+pub use ring::SyscallRing;
+pub use process::{SeccompArg, SeccompProg, TaskInfo};
+use crate::mm::MapAreaInfo;
+use crate::task::{
+    check_current_syscall, current_error_abi, current_task_label, exit_current_and_run_next, FilterAction, RLimit, TraceEntry,
+    SECCOMP_KILLED,
+};
 
-/// handle syscall exception with `syscall_id` and other arguments
-pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
+/// handle syscall exception with `syscall_id` and other arguments, return
+/// the value for `a0`
+pub fn syscall(syscall_id: usize, args: [usize; 6]) -> isize {
+    let result = match check_current_syscall(syscall_id, &args) {
+        Ok(()) => dispatch(syscall_id, args),
+        Err(FilterAction::Errno) => Err(Errno::EPERM),
+        Err(FilterAction::Kill) => {
+            println!(
+                "[kernel] Syscall {} denied by seccomp in application {}, killed.",
+                syscall_id,
+                current_task_label(),
+            );
+            exit_current_and_run_next(SECCOMP_KILLED);
+            panic!("Unreachable in syscall!");
+        }
+    };
+    errno::encode(result, syscall_id, current_error_abi())
+}
+
+fn dispatch(syscall_id: usize, args: [usize; 6]) -> SyscallResult {
     match syscall_id {
         SYSCALL_READ => sys_read(args[0], args[1] as *const u8, args[2]),
         SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
         SYSCALL_EXIT => sys_exit(args[0] as i32),
         SYSCALL_YIELD => sys_yield(),
         SYSCALL_GETPID => sys_getpid(),
+        SYSCALL_GETPPID => sys_getppid(),
         SYSCALL_FORK => sys_fork(),
         SYSCALL_EXEC => sys_exec(args[0] as *const u8),
-        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
+        SYSCALL_WAITPID => {
+            sys_waitpid(args[0] as isize, args[1] as *mut i32, args[2] as *mut ExitInfo)
+        }
         SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
-        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2]),
+        SYSCALL_CLOCK_GETTIME => sys_clock_gettime(args[0], args[1] as *mut TimeSpec),
+        SYSCALL_PTRACE => sys_ptrace(args[0], args[1], args[2], args[3]),
+        SYSCALL_TIMES => sys_times(args[0] as *mut Tms),
+        SYSCALL_SETPGID => sys_setpgid(args[0], args[1]),
+        SYSCALL_GETPGID => sys_getpgid(args[0]),
+        SYSCALL_GETSID => sys_getsid(args[0]),
+        SYSCALL_SETSID => sys_setsid(),
+        SYSCALL_GETRUSAGE => sys_getrusage(args[0] as isize, args[1] as *mut RUsage),
+        SYSCALL_PRCTL => sys_prctl(args[0], args[1]),
+        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2], args[3], args[4], args[5]),
         SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
+        SYSCALL_MADVISE => sys_madvise(args[0], args[1], args[2]),
         SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
         SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
         SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
-        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+        SYSCALL_TASK_MAPS => sys_task_maps(args[0], args[1] as *mut MapAreaInfo, args[2]),
+        SYSCALL_MEMINFO => sys_meminfo(args[0] as isize, args[1] as *mut MemInfo),
+        SYSCALL_GETRLIMIT => sys_getrlimit(args[0], args[1] as *mut RLimit),
+        SYSCALL_SETRLIMIT => sys_setrlimit(args[0], args[1] as *const RLimit),
+        SYSCALL_TRACE => sys_trace(args[0] as isize, args[1]),
+        SYSCALL_TRACE_READ => sys_trace_read(args[0] as isize, args[1] as *mut TraceEntry, args[2]),
+        SYSCALL_SECCOMP => sys_seccomp(args[0] as *const SeccompProg),
+        SYSCALL_PROC_LIST => sys_proc_list(args[0] as *mut ProcInfo, args[1]),
+        SYSCALL_PROC_INFO => sys_proc_info(args[0], args[1] as *mut ProcInfo),
+        SYSCALL_COREDUMP => sys_coredump(args[0], args[1], args[2] as *mut u8, args[3]),
+        SYSCALL_RING_SETUP => sys_ring_setup(args[0]),
+        SYSCALL_RING_ENTER => sys_ring_enter(args[0]),
+        _ => {
+            debug!("[kernel] Unsupported syscall_id: {}", syscall_id);
+            Err(Errno::ENOSYS)
+        }
     }
 }
@@ -1,17 +1,55 @@
 //! Process management syscalls
 
 use crate::loader::get_app_data_by_name;
-use crate::mm::{translated_refmut, translated_str, };
-use crate::mm::{MapPermission, PageTable, VirtAddr, PhysAddr};
+use super::{ErrorAbi, Errno, SyscallResult};
+use crate::mm::{
+    frame_stats, heap_stats, MapAreaInfo, MapError, MapPermission, UserPtr, UserSlice, VirtAddr,
+};
 use crate::task::{
-    add_task, current_task, current_user_token, exit_current_and_run_next,
-    suspend_current_and_run_next, TaskStatus, get_current_task_info, current_mmap, current_munmap
+    add_task, all_tasks, current_task, current_user_token, exit_current_and_run_next, find_task,
+    oom_kill, current_error_abi,
+    suspend_current_and_run_next, TaskStatus, get_current_task_info, current_mmap, current_munmap,
+    RLimit, SyscallTrace, TaskControlBlock, TraceEntry, Usage, ArgOp, ArgPredicate, FilterAction,
+    SyscallFilter, FILTER_WORDS, MAX_ARG_PREDICATES, Comm, COMM_LEN, current_task_label,
+    Termination, stop_status,
 };
 
 
-use crate::timer::get_time_us;
-use alloc::sync::Arc;
-use crate::config::MAX_SYSCALL_NUM;
+use crate::timer::{get_realtime_ns, get_time_ns, get_time_us, TICKS_PER_SEC};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::mem::size_of;
+use crate::config::{HUGE_PAGE_SIZE, MAX_SYSCALL_NUM};
+
+/// changes are private to the process
+const MAP_PRIVATE: usize = 0x02;
+/// map at exactly `start`, which os5 always does
+const MAP_FIXED: usize = 0x10;
+/// not backed by a file, the only kind os5 supports
+const MAP_ANONYMOUS: usize = 0x20;
+/// back the region with 2 MiB megapages
+const MAP_HUGETLB: usize = 0x40000;
+/// let the pages fault as if their memory were broken
+const MADV_HWPOISON: usize = 100;
+
+/// wall-clock time, seeded from the RTC at boot
+const CLOCK_REALTIME: usize = 0;
+/// time since boot
+const CLOCK_MONOTONIC: usize = 1;
+/// CPU time used by the calling process
+const CLOCK_PROCESS_CPUTIME_ID: usize = 2;
+const NANO_PER_SEC: usize = 1_000_000_000;
+
+/// rename the calling process
+const PR_SET_NAME: usize = 15;
+/// read the name of the calling process into a buffer of [`COMM_LEN`] bytes
+const PR_GET_NAME: usize = 16;
+
+/// usage of the calling process
+const RUSAGE_SELF: isize = 0;
+/// usage of the reaped children of the calling process and their own
+/// reaped children
+const RUSAGE_CHILDREN: isize = -1;
 
 #[repr(C)]
 #[derive(Debug)]
@@ -20,6 +58,120 @@ pub struct TimeVal {
     pub usec: usize,
 }
 
+impl TimeVal {
+    fn from_ns(ns: usize) -> Self {
+        Self {
+            sec: ns / NANO_PER_SEC,
+            usec: ns % NANO_PER_SEC / 1000,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+/// resources used, see [`sys_getrusage`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct RUsage {
+    pub utime: TimeVal,
+    pub stime: TimeVal,
+    pub page_faults: usize,
+    pub voluntary_switches: usize,
+    pub involuntary_switches: usize,
+}
+
+impl From<Usage> for RUsage {
+    fn from(usage: Usage) -> Self {
+        Self {
+            utime: TimeVal::from_ns(usage.user_ns),
+            stime: TimeVal::from_ns(usage.system_ns),
+            page_faults: usage.page_faults,
+            voluntary_switches: usage.voluntary_switches,
+            involuntary_switches: usage.involuntary_switches,
+        }
+    }
+}
+
+/// how a child ended, see [`sys_waitpid`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct ExitInfo {
+    /// the wait status, encoded as in POSIX
+    pub status: i32,
+    /// `scause`, `stval` and `sepc` of the trap which killed the child, all
+    /// zero if it was not killed by a fault. Only `pc` is set for a stop.
+    pub cause: usize,
+    pub addr: usize,
+    pub pc: usize,
+}
+
+impl From<Termination> for ExitInfo {
+    fn from(termination: Termination) -> Self {
+        let fault = termination.fault().unwrap_or_default();
+        Self {
+            status: termination.status(),
+            cause: fault.cause,
+            addr: fault.addr,
+            pc: fault.pc,
+        }
+    }
+}
+
+/// one entry of the process list, see [`sys_proc_list`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct ProcInfo {
+    pub pid: usize,
+    /// -1 for the initial process
+    pub ppid: isize,
+    /// a [`TaskStatus`] as a number, from 0 for `UnInit` to 4 for `Stopped`
+    pub status: usize,
+    pub priority: usize,
+    pub stride: usize,
+    pub cpu_time_ns: usize,
+    pub pgid: usize,
+    pub sid: usize,
+    /// nul-terminated unless all [`COMM_LEN`] bytes are used
+    pub name: [u8; COMM_LEN],
+}
+
+impl ProcInfo {
+    fn of(task: &TaskControlBlock, now_ns: usize) -> Self {
+        let inner = task.inner_exclusive_access();
+        let ppid = match inner.parent.as_ref().and_then(|parent| parent.upgrade()) {
+            Some(parent) => parent.getpid() as isize,
+            None => -1,
+        };
+        Self {
+            pid: task.getpid(),
+            ppid,
+            status: inner.task_status as usize,
+            priority: inner.priority,
+            stride: inner.stride,
+            cpu_time_ns: inner.total_cpu_time_ns(now_ns),
+            pgid: inner.pgid,
+            sid: inner.sid,
+            name: *inner.comm.as_bytes(),
+        }
+    }
+}
+
+/// CPU times in clock ticks of `1 / TICKS_PER_SEC` seconds, see [`sys_times`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct Tms {
+    pub utime: usize,
+    pub stime: usize,
+    /// of the reaped children
+    pub cutime: usize,
+    pub cstime: usize,
+}
+
 #[derive(Clone, Copy)]
 pub struct TaskInfo {
     pub status: TaskStatus,
@@ -27,36 +179,160 @@ pub struct TaskInfo {
     pub time: usize,
 }
 
-pub fn get_pa(va: usize) -> usize{ 
-    let current_token = current_user_token();
-    let pt: PageTable = PageTable::from_token(current_token);
-    let va: VirtAddr = VirtAddr::from(va);  
-    let ppn = pt.translate(va.floor()).unwrap().ppn();
-    let pa: PhysAddr = ppn.into();
-    let pa: usize = pa.into();
-    pa + va.page_offset()
+#[repr(C)]
+#[derive(Debug)]
+pub struct MemInfo {
+    pub total_frames: usize,
+    pub free_frames: usize,
+    pub heap_total: usize,
+    pub heap_used: usize,
+    /// resident pages of the queried process
+    pub rss: usize,
+}
+
+/// syscall filter handed over to `sys_seccomp`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SeccompProg {
+    /// bit `id` is set for each allowed syscall
+    pub allowed: [u64; FILTER_WORDS],
+    /// 0 to fail rejected syscalls with `EPERM`, 1 to kill the process
+    pub action: usize,
+    pub npredicates: usize,
+    /// array of `npredicates` [`SeccompArg`]
+    pub predicates: *const SeccompArg,
+}
+
+/// `args[arg] op value` must hold for syscall `id`, see [`ArgOp`] for `op`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SeccompArg {
+    pub id: usize,
+    pub arg: usize,
+    pub op: usize,
+    pub value: usize,
+}
+
+/// Run `alloc` again each time the OOM killer manages to reclaim memory.
+fn retry_on_oom<T>(mut alloc: impl FnMut() -> Option<T>) -> Option<T> {
+    loop {
+        if let Some(result) = alloc() {
+            return Some(result);
+        }
+        if !oom_kill() {
+            return None;
+        }
+    }
 }
 
 pub fn sys_exit(exit_code: i32) -> ! {
-    debug!("[kernel] Application exited with code {}", exit_code);
-    exit_current_and_run_next(exit_code);
+    debug!(
+        "[kernel] Application {} exited with code {}",
+        current_task_label(),
+        exit_code
+    );
+    exit_current_and_run_next(Termination::Exited(exit_code));
     panic!("Unreachable in sys_exit!");
 }
 
 /// current task gives up resources for other tasks
-pub fn sys_yield() -> isize {
+pub fn sys_yield() -> SyscallResult {
     suspend_current_and_run_next();
-    0
+    Ok(0)
+}
+
+pub fn sys_getpid() -> SyscallResult {
+    Ok(current_task().unwrap().pid.0)
+}
+
+/// Return the pid of the parent, or 0 for `INITPROC`, which has none.
+pub fn sys_getppid() -> SyscallResult {
+    let task = current_task().unwrap();
+    let parent = task.inner_exclusive_access().parent.as_ref().and_then(Weak::upgrade);
+    Ok(parent.map_or(0, |parent| parent.getpid()))
+}
+
+/// The caller if `pid` is 0, like the process group calls take it, else
+/// any process not reaped yet.
+fn task_or_current(pid: usize) -> Result<Arc<TaskControlBlock>, Errno> {
+    if pid == 0 {
+        Ok(current_task().unwrap())
+    } else {
+        find_task(pid).ok_or(Errno::ESRCH)
+    }
+}
+
+/// Whether a live process of session `sid` is in process group `pgid`.
+fn group_exists(pgid: usize, sid: usize) -> bool {
+    all_tasks().iter().any(|task| {
+        let inner = task.inner_exclusive_access();
+        !inner.is_zombie() && inner.pgid == pgid && inner.sid == sid
+    })
+}
+
+/// Move the caller or one of its children (`pid`, the caller if 0) into
+/// group `pgid`, a new one named after the process if `pgid` is 0 or its own
+/// pid. The group must be in the caller's session, and session leaders
+/// can't move.
+pub fn sys_setpgid(pid: usize, pgid: usize) -> SyscallResult {
+    let task = current_task().unwrap();
+    let target = if pid == 0 || pid == task.getpid() {
+        task.clone()
+    } else {
+        let inner = task.inner_exclusive_access();
+        let child = inner.children.iter().find(|child| child.getpid() == pid);
+        child.cloned().ok_or(Errno::ESRCH)?
+    };
+    let pgid = if pgid == 0 { target.getpid() } else { pgid };
+    let sid = task.inner_exclusive_access().sid;
+    let target_sid = target.inner_exclusive_access().sid;
+    if target_sid != sid || target_sid == target.getpid() {
+        return Err(Errno::EPERM);
+    }
+    if pgid != target.getpid() && !group_exists(pgid, sid) {
+        return Err(Errno::EPERM);
+    }
+    target.inner_exclusive_access().pgid = pgid;
+    Ok(0)
+}
+
+pub fn sys_getpgid(pid: usize) -> SyscallResult {
+    let task = task_or_current(pid)?;
+    let pgid = task.inner_exclusive_access().pgid;
+    Ok(pgid)
 }
 
-pub fn sys_getpid() -> isize {
-    current_task().unwrap().pid.0 as isize
+pub fn sys_getsid(pid: usize) -> SyscallResult {
+    let task = task_or_current(pid)?;
+    let sid = task.inner_exclusive_access().sid;
+    Ok(sid)
+}
+
+/// Start a new session and process group, both named after the caller,
+/// unless some group already has that name. Return the new session id.
+pub fn sys_setsid() -> SyscallResult {
+    let task = current_task().unwrap();
+    let pid = task.getpid();
+    let taken = all_tasks().iter().any(|other| {
+        let inner = other.inner_exclusive_access();
+        !inner.is_zombie() && inner.pgid == pid
+    });
+    if taken {
+        return Err(Errno::EPERM);
+    }
+    let mut inner = task.inner_exclusive_access();
+    inner.pgid = pid;
+    inner.sid = pid;
+    Ok(pid)
 }
 
 /// Syscall Fork which returns 0 for child process and child_pid for parent process
-pub fn sys_fork() -> isize {
+pub fn sys_fork() -> SyscallResult {
     let current_task = current_task().unwrap();
-    let new_task = current_task.fork();
+    if !current_task.inner_exclusive_access().may_add_child() {
+        return Err(Errno::EAGAIN);
+    }
+    let new_task = retry_on_oom(|| current_task.fork()).ok_or(Errno::ENOMEM)?;
     let new_pid = new_task.pid.0;
     // modify trap context of new_task, because it returns immediately after switching
     let trap_cx = new_task.inner_exclusive_access().get_trap_cx();
@@ -65,128 +341,499 @@ pub fn sys_fork() -> isize {
     trap_cx.x[10] = 0;
     // add new task to scheduler
     add_task(new_task);
-    new_pid as isize
+    Ok(new_pid)
 }
 
 /// Syscall Exec which accepts the elf path
-pub fn sys_exec(path: *const u8) -> isize {
-    let token = current_user_token();
-    let path = translated_str(token, path);
-    if let Some(data) = get_app_data_by_name(path.as_str()) {
-        let task = current_task().unwrap();
-        task.exec(data);
-        0
-    } else {
-        -1
-    }
+pub fn sys_exec(path: *const u8) -> SyscallResult {
+    let path = UserPtr::new(current_user_token(), path).read_str()?;
+    let data = get_app_data_by_name(path.as_str()).ok_or(Errno::ENOENT)?;
+    let task = current_task().unwrap();
+    retry_on_oom(|| task.exec(path.as_str(), data)).ok_or(Errno::ENOMEM)?;
+    Ok(0)
 }
 
-/// If there is not a child process whose pid is same as given, return -1.
-/// Else if there is a child process but it is still running, return -2.
-pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
+/// If there is not a child process matching `pid`, fail with `ECHILD`.
+/// Else if there is a matching child but it is still running, fail with
+/// `EAGAIN`. Store the exit code of the child at `exit_code_ptr`, and how it
+/// ended at `info_ptr`, unless they are null. The child is not reaped if
+/// either can't be stored. Programs of the labs see `EAGAIN` as -2 and
+/// `ECHILD` as -1, see [`ErrorAbi`].
+///
+/// A `pid` of -1 matches any child, 0 any child in the caller's process
+/// group, and below -1 any child in group `-pid`.
+pub fn sys_waitpid(
+    pid: isize,
+    exit_code_ptr: *mut i32,
+    info_ptr: *mut ExitInfo,
+) -> SyscallResult {
     let task = current_task().unwrap();
     // find a child process
 
     // ---- access current TCB exclusively
     let mut inner = task.inner_exclusive_access();
+    let own_pgid = inner.pgid;
+    let matches = |child: &TaskControlBlock, child_pgid: usize| match pid {
+        -1 => true,
+        0 => child_pgid == own_pgid,
+        pid if pid < -1 => child_pgid == pid.unsigned_abs(),
+        pid => child.getpid() == pid as usize,
+    };
     if !inner
         .children
         .iter()
-        .any(|p| pid == -1 || pid as usize == p.getpid())
+        .any(|p| matches(p, p.inner_exclusive_access().pgid))
     {
-        return -1;
+        return Err(Errno::ECHILD);
         // ---- release current PCB
     }
+    // a traced child reports each of its stops once, and is not reaped
+    let stopped = inner.children.iter().find_map(|p| {
+        let mut child_inner = p.inner_exclusive_access();
+        if !matches(p, child_inner.pgid) {
+            return None;
+        }
+        let signal = child_inner.tracee.as_mut()?.take_unreported_stop()?;
+        Some((p.getpid(), signal, child_inner.get_trap_cx().sepc))
+    });
+    if let Some((child_pid, signal, pc)) = stopped {
+        let status = stop_status(signal);
+        let exit_code_ptr = UserPtr::new(inner.memory_set.token(), exit_code_ptr);
+        if !exit_code_ptr.is_null() {
+            exit_code_ptr.write(&status)?;
+        }
+        let info_ptr = UserPtr::new(inner.memory_set.token(), info_ptr);
+        if !info_ptr.is_null() {
+            info_ptr.write(&ExitInfo {
+                status,
+                cause: 0,
+                addr: 0,
+                pc,
+            })?;
+        }
+        return Ok(child_pid);
+    }
     let pair = inner.children.iter().enumerate().find(|(_, p)| {
         // ++++ temporarily access child PCB lock exclusively
-        p.inner_exclusive_access().is_zombie() && (pid == -1 || pid as usize == p.getpid())
+        let child_inner = p.inner_exclusive_access();
+        child_inner.is_zombie() && matches(p, child_inner.pgid)
         // ++++ release child PCB
     });
-    if let Some((idx, _)) = pair {
-        let child = inner.children.remove(idx);
-        // confirm that child will be deallocated after removing from children list
-        assert_eq!(Arc::strong_count(&child), 1);
-        let found_pid = child.getpid();
-        // ++++ temporarily access child TCB exclusively
-        let exit_code = child.inner_exclusive_access().exit_code;
-        // ++++ release child PCB
-        *translated_refmut(inner.memory_set.token(), exit_code_ptr) = exit_code;
-        found_pid as isize
-    } else {
-        -2
+    let (idx, child) = pair.ok_or(Errno::EAGAIN)?;
+    // ++++ temporarily access child TCB exclusively
+    let (termination, usage) = {
+        let child_inner = child.inner_exclusive_access();
+        let mut usage = child_inner.children_usage;
+        usage.add(&child_inner.accounting.usage);
+        (child_inner.termination, usage)
+    };
+    // ++++ release child PCB
+    let exit_code_ptr = UserPtr::new(inner.memory_set.token(), exit_code_ptr);
+    if !exit_code_ptr.is_null() {
+        exit_code_ptr.write(&termination.exit_code())?;
+    }
+    let info_ptr = UserPtr::new(inner.memory_set.token(), info_ptr);
+    if !info_ptr.is_null() {
+        info_ptr.write(&ExitInfo::from(termination))?;
+    }
+    let child = inner.children.remove(idx);
+    inner.children_usage.add(&usage);
+    if let Some(core) = child.inner_exclusive_access().core.take() {
+        inner.child_core = Some((child.getpid(), core));
     }
+    // confirm that child will be deallocated after removing from children list
+    assert_eq!(Arc::strong_count(&child), 1);
+    Ok(child.getpid())
     // ---- release current PCB lock automatically
 }
 
 // YOUR JOB: 引入虚地址后重写 sys_get_time
-pub fn sys_get_time(_ts: *mut TimeVal, _tz: usize) -> isize {
+pub fn sys_get_time(_ts: *mut TimeVal, _tz: usize) -> SyscallResult {
     let _us = get_time_us();
-    let pa = get_pa(_ts as usize);
-    unsafe{
-        let time = pa as *mut TimeVal;
-        * time = TimeVal {
-            sec: _us / 1_000_000,
-            usec: _us % 1_000_000,
-        }
-    }
-    0
+    let time = TimeVal {
+        sec: _us / 1_000_000,
+        usec: _us % 1_000_000,
+    };
+    UserPtr::new(current_user_token(), _ts).write(&time)?;
+    Ok(0)
+}
+
+/// Read the clock `clock_id`, one of `CLOCK_REALTIME`, `CLOCK_MONOTONIC` and
+/// `CLOCK_PROCESS_CPUTIME_ID`.
+pub fn sys_clock_gettime(clock_id: usize, tp: *mut TimeSpec) -> SyscallResult {
+    let ns = match clock_id {
+        CLOCK_REALTIME => get_realtime_ns(),
+        CLOCK_MONOTONIC => get_time_ns(),
+        CLOCK_PROCESS_CPUTIME_ID => current_task()
+            .unwrap()
+            .inner_exclusive_access()
+            .total_cpu_time_ns(get_time_ns()),
+        _ => return Err(Errno::EINVAL),
+    };
+    let time = TimeSpec {
+        sec: ns / NANO_PER_SEC,
+        nsec: ns % NANO_PER_SEC,
+    };
+    UserPtr::new(current_user_token(), tp).write(&time)?;
+    Ok(0)
+}
+
+/// Report the resources used by the calling process or its children,
+/// depending on `who`.
+pub fn sys_getrusage(who: isize, usage: *mut RUsage) -> SyscallResult {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let used = match who {
+        RUSAGE_SELF => inner.accounting.current(get_time_ns()),
+        RUSAGE_CHILDREN => inner.children_usage,
+        _ => return Err(Errno::EINVAL),
+    };
+    UserPtr::new(inner.get_user_token(), usage).write(&RUsage::from(used))?;
+    Ok(0)
+}
+
+/// Report the CPU times of the calling process and its reaped children,
+/// return the clock ticks since boot.
+pub fn sys_times(buf: *mut Tms) -> SyscallResult {
+    const NANO_PER_TICK: usize = NANO_PER_SEC / TICKS_PER_SEC;
+    let now = get_time_ns();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let own = inner.accounting.current(now);
+    let tms = Tms {
+        utime: own.user_ns / NANO_PER_TICK,
+        stime: own.system_ns / NANO_PER_TICK,
+        cutime: inner.children_usage.user_ns / NANO_PER_TICK,
+        cstime: inner.children_usage.system_ns / NANO_PER_TICK,
+    };
+    UserPtr::new(inner.get_user_token(), buf).write(&tms)?;
+    Ok(now / NANO_PER_TICK)
 }
 
 // YOUR JOB: 引入虚地址后重写 sys_task_info
-pub fn sys_task_info(ti: *mut TaskInfo) -> isize {
-    let pa = get_pa(ti as usize);
-    get_current_task_info(pa as *mut TaskInfo)
+pub fn sys_task_info(ti: *mut TaskInfo) -> SyscallResult {
+    UserPtr::new(current_user_token(), ti).write(&get_current_task_info())?;
+    Ok(0)
 }
 
 // YOUR JOB: 实现sys_set_priority，为任务添加优先级
-pub fn sys_set_priority(prio: isize) -> isize {
+pub fn sys_set_priority(prio: isize) -> SyscallResult {
     if prio >= 2 {
         let task = current_task().unwrap();
         let mut inner = task.inner_exclusive_access();
         inner.priority = prio as usize;
-        prio
+        Ok(prio as usize)
     } else {
-        -1
+        Err(Errno::EINVAL)
     }
 }
 
 // YOUR JOB: 扩展内核以实现 sys_mmap 和 sys_munmap
-pub fn sys_mmap(_start: usize, _len: usize, _port: usize) -> isize {
+/// Only anonymous private mappings exist, so `fd` must be -1 and `offset` 0.
+/// No `flags` at all is the three argument mmap of the labs, which maps
+/// anonymous memory at `start` whatever `fd` and `offset`. The programs of
+/// the labs leave `a3` to `a5` as they happen to be, so the last three
+/// arguments are ignored for them.
+pub fn sys_mmap(
+    _start: usize,
+    _len: usize,
+    _port: usize,
+    flags: usize,
+    fd: usize,
+    offset: usize,
+) -> SyscallResult {
+    let flags = match current_error_abi() {
+        ErrorAbi::Legacy => 0,
+        ErrorAbi::Errno => flags,
+    };
+    let (flags, fd, offset) = match flags {
+        0 => (MAP_PRIVATE | MAP_FIXED | MAP_ANONYMOUS, usize::MAX, 0),
+        flags => (flags, fd, offset),
+    };
+    if flags & !(MAP_PRIVATE | MAP_FIXED | MAP_ANONYMOUS | MAP_HUGETLB) != 0 {
+        return Err(Errno::EINVAL);
+    }
+    if flags & MAP_ANONYMOUS == 0 {
+        return Err(Errno::EBADF);
+    }
+    if fd as isize != -1 || offset != 0 {
+        return Err(Errno::EINVAL);
+    }
     let va = VirtAddr(_start);
     if !va.aligned() {
-        return -1
+        return Err(Errno::EINVAL);
+    }
+    let huge = flags & MAP_HUGETLB != 0;
+    if huge && _start % HUGE_PAGE_SIZE != 0 {
+        return Err(Errno::EINVAL);
     }
     if ((_port & 0x7) == 0) || _port > 7 {
-        return -1
+        return Err(Errno::EINVAL);
     }
     let perm = MapPermission::from_bits(((_port<<1) + 16) as u8).unwrap();
-    current_mmap(va, _len, perm)
-
+    if !current_task().unwrap().inner_exclusive_access().may_map(_len) {
+        return Err(Errno::ENOMEM);
+    }
+    loop {
+        match current_mmap(va, _len, perm, huge) {
+            Ok(()) => return Ok(0),
+            Err(MapError::NoMemory) if oom_kill() => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
 }
 
-pub fn sys_munmap(_start: usize, _len: usize) -> isize {
+/// The syscall ring, if any, can't be unmapped.
+pub fn sys_munmap(_start: usize, _len: usize) -> SyscallResult {
     let va = VirtAddr(_start);
     if !va.aligned() {
-        return -1
+        return Err(Errno::EINVAL);
+    }
+    let ring = current_task().unwrap().inner_exclusive_access().ring;
+    if ring.map_or(false, |ring| ring.overlaps(_start, _len)) {
+        return Err(Errno::EINVAL);
     }
-    current_munmap(va, _len)
+    current_munmap(va, _len)?;
+    Ok(0)
 }
 
+/// Only `MADV_HWPOISON` is supported, to test how the kernel copes with user
+/// pages which are mapped but fault: their frames are freed, and any access
+/// to them faults from then on. The pages must lie in one mapping of 4 KiB
+/// pages.
+pub fn sys_madvise(start: usize, len: usize, advice: usize) -> SyscallResult {
+    if advice != MADV_HWPOISON {
+        return Err(Errno::EINVAL);
+    }
+    let va = VirtAddr(start);
+    if !va.aligned() || start.checked_add(len).is_none() {
+        return Err(Errno::EINVAL);
+    }
+    let task = current_task().unwrap();
+    task.inner_exclusive_access().memory_set.poison(va, len)?;
+    Ok(0)
+}
 
-pub fn sys_spawn(path: *const u8) -> isize {
+pub fn sys_spawn(path: *const u8) -> SyscallResult {
     let current_task = current_task().unwrap();
+    let path = UserPtr::new(current_user_token(), path).read_str()?;
+    let data = get_app_data_by_name(path.as_str()).ok_or(Errno::ENOENT)?;
+    if !current_task.inner_exclusive_access().may_add_child() {
+        return Err(Errno::EAGAIN);
+    }
+    let new_task = retry_on_oom(|| current_task.spawn(path.as_str(), data)).ok_or(Errno::ENOMEM)?;
+    let new_pid = new_task.pid.0;
+    add_task(new_task);
+    Ok(new_pid)
+}
+
+/// Fill `buf` with at most `len` areas of the memory set of process `pid`.
+/// Return the total number of areas.
+pub fn sys_task_maps(pid: usize, buf: *mut MapAreaInfo, len: usize) -> SyscallResult {
+    let task = find_task(pid).ok_or(Errno::ESRCH)?;
+    let areas = task.inner_exclusive_access().memory_set.areas_info();
+    let buf = UserPtr::new(current_user_token(), buf);
+    for (i, area) in areas.iter().take(len).enumerate() {
+        buf.add(i).write(area)?;
+    }
+    Ok(areas.len())
+}
+
+/// Describe at most `len` live or zombie processes in `buf`, in order of
+/// pid. Return how many there are in total.
+pub fn sys_proc_list(buf: *mut ProcInfo, len: usize) -> SyscallResult {
+    let now = get_time_ns();
+    let tasks = all_tasks();
+    let buf = UserPtr::new(current_user_token(), buf);
+    for (i, task) in tasks.iter().take(len).enumerate() {
+        buf.add(i).write(&ProcInfo::of(task, now))?;
+    }
+    Ok(tasks.len())
+}
+
+/// Describe the process `pid`, which need not be related to the caller.
+pub fn sys_proc_info(pid: usize, info: *mut ProcInfo) -> SyscallResult {
+    let task = find_task(pid).ok_or(Errno::ESRCH)?;
+    let info_now = ProcInfo::of(&task, get_time_ns());
+    UserPtr::new(current_user_token(), info).write(&info_now)?;
+    Ok(0)
+}
+
+/// Get or set the name of the calling process, which starts out as the name
+/// of its app. New names are cut to `COMM_LEN - 1` bytes.
+pub fn sys_prctl(option: usize, arg: usize) -> SyscallResult {
     let token = current_user_token();
-    let path = translated_str(token, path);
-    if let Some(data) = get_app_data_by_name(path.as_str()) {
-        let new_task = current_task.spawn(data);
-        let new_pid = new_task.pid.0;
-        add_task(new_task);
-        new_pid as isize
+    match option {
+        PR_SET_NAME => {
+            let name = UserPtr::new(token, arg as *const u8).read_str()?;
+            current_task().unwrap().inner_exclusive_access().comm =
+                Comm::from_bytes(name.as_bytes());
+        }
+        PR_GET_NAME => {
+            let comm = current_task().unwrap().comm();
+            UserPtr::new(token, arg as *mut [u8; COMM_LEN]).write(comm.as_bytes())?;
+        }
+        _ => return Err(Errno::EINVAL),
+    }
+    Ok(0)
+}
+
+/// Copy at most `len` bytes from `offset` on of the core dump of child `pid`
+/// to `buf`, and return the size of the whole dump. Only the dump of the
+/// child reaped last among those which left one is kept.
+pub fn sys_coredump(pid: usize, offset: usize, buf: *mut u8, len: usize) -> SyscallResult {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let core = match &inner.child_core {
+        Some((core_pid, core)) if *core_pid == pid => core,
+        _ => return Err(Errno::ENOENT),
+    };
+    let start = offset.min(core.len());
+    let end = start + len.min(core.len() - start);
+    UserSlice::new(inner.memory_set.token(), buf, end - start).write_from(&core[start..end])?;
+    Ok(core.len())
+}
+
+/// Report frame and kernel heap usage, along with the resident pages of
+/// process `pid` (the caller if `pid` is -1).
+pub fn sys_meminfo(pid: isize, info: *mut MemInfo) -> SyscallResult {
+    let task = if pid == -1 {
+        current_task()
     } else {
-        -1
+        find_task(pid as usize)
+    };
+    let rss = task
+        .ok_or(Errno::ESRCH)?
+        .inner_exclusive_access()
+        .memory_set
+        .resident_pages();
+    let (total_frames, free_frames) = frame_stats();
+    let (heap_total, heap_used) = heap_stats();
+    UserPtr::new(current_user_token(), info).write(&MemInfo {
+        total_frames,
+        free_frames,
+        heap_total,
+        heap_used,
+        rss,
+    })?;
+    Ok(0)
+}
+
+/// Read the limits of `resource` for the current process.
+pub fn sys_getrlimit(resource: usize, rlim: *mut RLimit) -> SyscallResult {
+    let limit = current_task().unwrap().inner_exclusive_access().rlimits.get(resource);
+    let limit = limit.ok_or(Errno::EINVAL)?;
+    UserPtr::new(current_user_token(), rlim).write(&limit)?;
+    Ok(0)
+}
+
+/// Change the limits of `resource` for the current process and the children
+/// it creates afterwards. The hard limit can only be lowered.
+pub fn sys_setrlimit(resource: usize, rlim: *const RLimit) -> SyscallResult {
+    let limit = UserPtr::new(current_user_token(), rlim).read()?;
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    inner.rlimits.set(resource, limit)?;
+    Ok(0)
+}
+
+/// the current task if `pid` is -1, otherwise one of its children
+fn self_or_child(pid: isize) -> Option<Arc<TaskControlBlock>> {
+    let task = current_task().unwrap();
+    if pid == -1 {
+        return Some(task);
+    }
+    let inner = task.inner_exclusive_access();
+    let child = inner
+        .children
+        .iter()
+        .find(|child| child.getpid() == pid as usize)
+        .cloned();
+    child
+}
+
+/// Turn syscall tracing of the current process (`pid` -1) or one of its
+/// children on or off. Children created afterwards inherit the setting.
+pub fn sys_trace(pid: isize, enable: usize) -> SyscallResult {
+    let task = self_or_child(pid).ok_or(Errno::ESRCH)?;
+    let mut inner = task.inner_exclusive_access();
+    if enable == 0 {
+        inner.trace = None;
+    } else if inner.trace.is_none() {
+        inner.trace = Some(SyscallTrace::new());
     }
+    Ok(0)
+}
 
+/// Move at most `len` logged syscalls of the current process (`pid` -1) or
+/// one of its children into `buf`, oldest first. Return how many were moved.
+/// Fail with `ESRCH` once the child has exited and its log is empty, so that
+/// the tracer knows it may reap the child.
+pub fn sys_trace_read(pid: isize, buf: *mut TraceEntry, len: usize) -> SyscallResult {
+    let token = current_user_token();
+    let size = len.checked_mul(size_of::<TraceEntry>()).ok_or(Errno::EFAULT)?;
+    // check first so that no entry is lost
+    UserSlice::new(token, buf as *const u8, size).writable_buffers()?;
+    let task = self_or_child(pid).ok_or(Errno::ESRCH)?;
+    let entries = {
+        let mut inner = task.inner_exclusive_access();
+        // a zombie never finishes its `exit`
+        let exited = inner.is_zombie();
+        let entries = match inner.trace.as_mut() {
+            Some(trace) => trace.drain(len, exited),
+            None => return Err(Errno::EINVAL),
+        };
+        if exited && entries.is_empty() {
+            return Err(Errno::ESRCH);
+        }
+        entries
+    };
+    let buf = UserPtr::new(token, buf);
+    for (i, entry) in entries.iter().enumerate() {
+        buf.add(i).write(entry)?;
+    }
+    Ok(entries.len())
+}
 
-    
-    
+/// Restrict the syscalls of the current process and of the children it
+/// creates afterwards to those allowed by `prog`, on top of any filter
+/// installed before.
+pub fn sys_seccomp(prog: *const SeccompProg) -> SyscallResult {
+    let token = current_user_token();
+    let prog = UserPtr::new(token, prog).read()?;
+    let action = match prog.action {
+        0 => FilterAction::Errno,
+        1 => FilterAction::Kill,
+        _ => return Err(Errno::EINVAL),
+    };
+    if prog.npredicates > MAX_ARG_PREDICATES {
+        return Err(Errno::EINVAL);
+    }
+    let args = UserPtr::new(token, prog.predicates);
+    let mut predicates = Vec::with_capacity(prog.npredicates);
+    for i in 0..prog.npredicates {
+        let arg = args.add(i).read()?;
+        let op = ArgOp::from_usize(arg.op).ok_or(Errno::EINVAL)?;
+        if arg.id >= MAX_SYSCALL_NUM || arg.arg >= 6 {
+            return Err(Errno::EINVAL);
+        }
+        predicates.push(ArgPredicate {
+            id: arg.id,
+            arg: arg.arg,
+            op,
+            value: arg.value,
+        });
+    }
+    let filter = SyscallFilter::new(prog.allowed, predicates, action);
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let filter = match inner.filter.as_ref() {
+        Some(old) => old.intersect(&filter),
+        None => filter,
+    };
+    if filter.predicate_count() > MAX_ARG_PREDICATES {
+        return Err(Errno::EINVAL);
+    }
+    inner.filter = Some(filter);
+    Ok(0)
 }
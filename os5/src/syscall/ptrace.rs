@@ -0,0 +1,144 @@
+//! Tracing of a child by a debugger
+//!
+//! A child asks to be traced with `PTRACE_TRACEME`, or its parent takes it
+//! over with `PTRACE_ATTACH`. The parent learns of each stop through
+//! `waitpid`, then inspects and changes the stopped child before letting it
+//! go on. Stops and single steps are described in [`crate::task`].
+
+use super::{Errno, SyscallResult};
+use crate::mm::UserPtr;
+use crate::task::{
+    current_task, current_user_token, read_user, resume, write_user, TaskControlBlock, TaskStatus,
+    Termination, Tracee, SIGKILL, SIGSTOP,
+};
+use alloc::sync::Arc;
+
+/// mark the caller as traced by its parent
+pub const PTRACE_TRACEME: usize = 0;
+/// read the doubleword at `addr` of the child into `*data`
+pub const PTRACE_PEEKDATA: usize = 2;
+/// write `data` as the doubleword at `addr` of the child
+pub const PTRACE_POKEDATA: usize = 5;
+/// resume the child
+pub const PTRACE_CONT: usize = 7;
+/// kill the child, which needs not be stopped
+pub const PTRACE_KILL: usize = 8;
+/// resume the child for one instruction
+pub const PTRACE_SINGLESTEP: usize = 9;
+/// copy the registers of the child into the [`UserRegs`] at `data`
+pub const PTRACE_GETREGS: usize = 12;
+/// set the registers of the child from the [`UserRegs`] at `data`
+pub const PTRACE_SETREGS: usize = 13;
+/// trace the child, which stops at once
+pub const PTRACE_ATTACH: usize = 16;
+/// stop tracing the child and resume it
+pub const PTRACE_DETACH: usize = 17;
+
+/// the registers of a stopped task, as `struct user_regs_struct` of Linux
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct UserRegs {
+    pub pc: usize,
+    /// `x1` to `x31`
+    pub x: [usize; 31],
+}
+
+/// Carry out `request` on the child `pid` of the caller. Apart from
+/// `PTRACE_TRACEME`, `PTRACE_ATTACH` and `PTRACE_KILL`, the child must be
+/// traced and stopped.
+pub fn sys_ptrace(request: usize, pid: usize, addr: usize, data: usize) -> SyscallResult {
+    if request == PTRACE_TRACEME {
+        let task = current_task().unwrap();
+        let mut inner = task.inner_exclusive_access();
+        if inner.parent.is_none() || inner.tracee.is_some() {
+            return Err(Errno::EPERM);
+        }
+        inner.tracee = Some(Tracee::default());
+        return Ok(0);
+    }
+    let child = traced_child(pid)?;
+    let mut child_inner = child.inner_exclusive_access();
+    if request == PTRACE_ATTACH {
+        if child_inner.is_zombie() || child_inner.tracee.is_some() {
+            return Err(Errno::EPERM);
+        }
+        child_inner.tracee = Some(Tracee {
+            pending_stop: Some(SIGSTOP),
+            ..Tracee::default()
+        });
+        return Ok(0);
+    }
+    let stopped = child_inner.task_status == TaskStatus::Stopped;
+    if child_inner.tracee.is_none() || (!stopped && request != PTRACE_KILL) {
+        return Err(Errno::ESRCH);
+    }
+    let inner = &mut *child_inner;
+    let trap_cx = inner.get_trap_cx();
+    match request {
+        PTRACE_PEEKDATA => {
+            let mut word = [0u8; 8];
+            read_user(&inner.memory_set, addr, &mut word).ok_or(Errno::EFAULT)?;
+            drop(child_inner);
+            UserPtr::new(current_user_token(), data as *mut u64).write(&u64::from_le_bytes(word))?;
+        }
+        PTRACE_POKEDATA => {
+            write_user(&inner.memory_set, addr, &(data as u64).to_le_bytes())
+                .ok_or(Errno::EFAULT)?;
+        }
+        PTRACE_GETREGS => {
+            let mut regs = UserRegs {
+                pc: trap_cx.sepc,
+                x: [0; 31],
+            };
+            regs.x.copy_from_slice(&trap_cx.x[1..]);
+            drop(child_inner);
+            UserPtr::new(current_user_token(), data as *mut UserRegs).write(&regs)?;
+        }
+        PTRACE_SETREGS => {
+            drop(child_inner);
+            let regs = UserPtr::new(current_user_token(), data as *const UserRegs).read()?;
+            trap_cx.sepc = regs.pc;
+            trap_cx.x[1..].copy_from_slice(&regs.x);
+        }
+        PTRACE_CONT => {
+            drop(child_inner);
+            resume(&child);
+        }
+        PTRACE_SINGLESTEP => {
+            inner
+                .tracee
+                .as_mut()
+                .unwrap()
+                .add_step_breakpoints(&inner.memory_set, trap_cx.sepc, &trap_cx.x)
+                .ok_or(Errno::EFAULT)?;
+            drop(child_inner);
+            resume(&child);
+        }
+        PTRACE_KILL => {
+            inner.killed = Some(Termination::Killed(SIGKILL));
+            drop(child_inner);
+            resume(&child);
+        }
+        PTRACE_DETACH => {
+            if let Some(mut tracee) = inner.tracee.take() {
+                tracee.remove_step_breakpoints(&inner.memory_set);
+            }
+            drop(child_inner);
+            resume(&child);
+        }
+        _ => return Err(Errno::EINVAL),
+    }
+    Ok(0)
+}
+
+/// The child `pid` of the caller, the only process it may trace.
+fn traced_child(pid: usize) -> Result<Arc<TaskControlBlock>, Errno> {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    inner
+        .children
+        .iter()
+        .find(|child| child.getpid() == pid)
+        .cloned()
+        .ok_or(Errno::ESRCH)
+}
@@ -0,0 +1,190 @@
+//! Batched syscalls through a submission/completion ring
+//!
+//! `sys_ring_setup` maps a ring shared with the kernel at [`RING_BASE`]: a
+//! [`RingHeader`], then `entries` [`Submission`]s, then `entries`
+//! [`Completion`]s. The process queues syscalls by filling submissions and
+//! moving `sq_tail`, and a single `sys_ring_enter` runs them in order, each
+//! leaving a completion behind. Head and tail only ever grow, the slot of
+//! an index is `index % entries`.
+//!
+//! The ring stays mapped until `exec`: `munmap` refuses to touch it, so that
+//! no completion is lost to a fault.
+
+use super::{errno, syscall, Errno, SyscallResult};
+use super::{SYSCALL_EXEC, SYSCALL_FORK, SYSCALL_RING_ENTER};
+use crate::config::{PAGE_SIZE, RING_BASE};
+use crate::mm::{MapError, MapPermission, UserPtr, VirtAddr};
+use crate::task::{
+    current_error_abi, current_mmap, current_task, current_user_token,
+    increase_current_task_syscall, oom_kill, trace_syscall_begin, trace_syscall_end,
+};
+use core::mem::size_of;
+
+/// entries a ring may have at most, a power of two
+pub const RING_MAX_ENTRIES: usize = 256;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct RingHeader {
+    pub entries: usize,
+    /// next submission the kernel takes
+    pub sq_head: usize,
+    /// moved by the process once a submission is filled in
+    pub sq_tail: usize,
+    /// moved by the process once a completion is consumed
+    pub cq_head: usize,
+    /// next completion the kernel fills in
+    pub cq_tail: usize,
+}
+
+/// a syscall to run, as if made with `ecall`
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Submission {
+    pub id: usize,
+    pub args: [usize; 6],
+    /// handed back in the completion
+    pub user_data: usize,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Completion {
+    pub user_data: usize,
+    /// what `a0` would hold after the syscall
+    pub result: isize,
+}
+
+/// the ring of a process, always mapped at [`RING_BASE`]
+#[derive(Copy, Clone)]
+pub struct SyscallRing {
+    entries: usize,
+}
+
+impl SyscallRing {
+    fn size(entries: usize) -> usize {
+        size_of::<RingHeader>() + entries * (size_of::<Submission>() + size_of::<Completion>())
+    }
+    /// bytes mapped for a ring of `entries` slots, whole pages
+    fn mapped_len(entries: usize) -> usize {
+        (Self::size(entries) + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE
+    }
+    /// Whether the `len` bytes from `start` on cover part of the ring.
+    pub fn overlaps(&self, start: usize, len: usize) -> bool {
+        start < RING_BASE + Self::mapped_len(self.entries) && RING_BASE < start.saturating_add(len)
+    }
+    fn header(&self, token: usize) -> UserPtr<RingHeader> {
+        UserPtr::new(token, RING_BASE as *const RingHeader)
+    }
+    fn submission(&self, token: usize, index: usize) -> UserPtr<Submission> {
+        let base = RING_BASE + size_of::<RingHeader>();
+        UserPtr::new(token, base as *const Submission).add(index % self.entries)
+    }
+    fn completion(&self, token: usize, index: usize) -> UserPtr<Completion> {
+        let base = RING_BASE + size_of::<RingHeader>() + self.entries * size_of::<Submission>();
+        UserPtr::new(token, base as *const Completion).add(index % self.entries)
+    }
+}
+
+/// Map a ring of `entries` slots, a power of two, and return its address.
+/// A process has at most one ring, kept across `fork` and dropped by `exec`.
+pub fn sys_ring_setup(entries: usize) -> SyscallResult {
+    if !entries.is_power_of_two() || entries > RING_MAX_ENTRIES {
+        return Err(Errno::EINVAL);
+    }
+    let task = current_task().unwrap();
+    let len = SyscallRing::mapped_len(entries);
+    {
+        let inner = task.inner_exclusive_access();
+        if inner.ring.is_some() {
+            return Err(Errno::EEXIST);
+        }
+        if !inner.may_map(len) {
+            return Err(Errno::ENOMEM);
+        }
+    }
+    let perm = MapPermission::R | MapPermission::W | MapPermission::U;
+    loop {
+        match current_mmap(VirtAddr(RING_BASE), len, perm, false) {
+            Ok(()) => break,
+            Err(MapError::NoMemory) if oom_kill() => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    let ring = SyscallRing { entries };
+    let header = RingHeader {
+        entries,
+        sq_head: 0,
+        sq_tail: 0,
+        cq_head: 0,
+        cq_tail: 0,
+    };
+    ring.header(current_user_token()).write(&header)?;
+    task.inner_exclusive_access().ring = Some(ring);
+    Ok(RING_BASE)
+}
+
+/// Run a queued syscall with the same bookkeeping as a trapped one.
+fn run(submission: &Submission) -> isize {
+    match submission.id {
+        // they would leave the ring half processed in another image
+        SYSCALL_FORK | SYSCALL_EXEC | SYSCALL_RING_ENTER => {
+            errno::encode(Err(Errno::EINVAL), submission.id, current_error_abi())
+        }
+        id => {
+            increase_current_task_syscall(id);
+            trace_syscall_begin(id, submission.args);
+            let result = syscall(id, submission.args);
+            trace_syscall_end(result);
+            result
+        }
+    }
+}
+
+/// Run at most `to_submit` queued syscalls, stopping early when the
+/// submission queue is empty or the completion queue full. Return how many
+/// were run. `fork` and `exec` complete with `EINVAL` without running.
+pub fn sys_ring_enter(to_submit: usize) -> SyscallResult {
+    let ring = current_task().unwrap().inner_exclusive_access().ring;
+    if ring.is_none() {
+        return Err(Errno::EINVAL);
+    }
+    let mut submitted = 0;
+    while submitted < to_submit {
+        // others may run here, or the task may end for its CPU time
+        #[cfg(feature = "kernel-preempt")]
+        crate::trap::preempt_point();
+        let task = current_task().unwrap();
+        let inner = task.inner_exclusive_access();
+        // e.g. chosen by the OOM killer, the rest waits for nobody
+        if inner.killed.is_some() {
+            break;
+        }
+        // looked up again after others ran
+        let (ring, token) = match inner.ring {
+            Some(ring) => (ring, inner.get_user_token()),
+            None => break,
+        };
+        drop(inner);
+        drop(task);
+        let header = ring.header(token).read()?;
+        if header.sq_head == header.sq_tail
+            || header.cq_tail.wrapping_sub(header.cq_head) >= ring.entries
+        {
+            break;
+        }
+        let submission = ring.submission(token, header.sq_head).read()?;
+        let result = run(&submission);
+        // the syscall may have written to the ring itself
+        let mut header = ring.header(token).read()?;
+        ring.completion(token, header.cq_tail).write(&Completion {
+            user_data: submission.user_data,
+            result,
+        })?;
+        header.sq_head = header.sq_head.wrapping_add(1);
+        header.cq_tail = header.cq_tail.wrapping_add(1);
+        ring.header(token).write(&header)?;
+        submitted += 1;
+    }
+    Ok(submitted)
+}
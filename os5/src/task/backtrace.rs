@@ -0,0 +1,203 @@
+//! Backtraces of user programs killed by a fault
+//!
+//! The apps are built with frame pointers, so each frame keeps the return
+//! address at `fp - 8` and the frame pointer of its caller at `fp - 16`.
+//! The chain is followed through the page table of the process, and each
+//! address is looked up in the `.symtab` of the ELF it was loaded from, if
+//! the symbols were not stripped. The same lines go to the console and into
+//! the core dump, where the parent can read them.
+
+use super::processor::current_task;
+use super::ptrace::read_user;
+use crate::mm::MemorySet;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use xmas_elf::sections::SectionData;
+use xmas_elf::symbol_table::{Entry, Type};
+use xmas_elf::ElfFile;
+
+/// frames printed at most, in case the chain loops or runs on
+const MAX_FRAMES: usize = 16;
+
+/// The frames of the current task, from the trapping `sepc` up, one line
+/// each.
+pub fn current_backtrace() -> String {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let trap_cx = inner.get_trap_cx();
+    let (frames, truncated) = walk(&inner.memory_set, trap_cx.sepc, trap_cx.x[8]);
+    let mut lines = String::new();
+    for (i, &pc) in frames.iter().enumerate() {
+        // a return address may already be past the end of the caller, as
+        // after a call to a function which does not return
+        let lookup = if i == 0 { pc } else { pc - 1 };
+        // writing to a string does not fail
+        let _ = match symbolize(inner.image, lookup) {
+            Some((name, start)) => writeln!(
+                lines,
+                "#{:<2} {:#018x} in {}+{:#x}",
+                i,
+                pc,
+                Demangle(name),
+                pc - start
+            ),
+            None => writeln!(lines, "#{:<2} {:#018x}", i, pc),
+        };
+    }
+    if truncated {
+        lines.push_str("...\n");
+    }
+    lines
+}
+
+/// Print `backtrace`, from [`current_backtrace`].
+pub fn print_current_backtrace(backtrace: &str) {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    println!(
+        "[kernel] Backtrace of {} (pid {}):",
+        inner.comm,
+        task.getpid()
+    );
+    for line in backtrace.lines() {
+        println!("[kernel]   {}", line);
+    }
+}
+
+fn read_usize(memory_set: &MemorySet, va: usize) -> Option<usize> {
+    let mut buf = [0u8; 8];
+    read_user(memory_set, va, &mut buf)?;
+    Some(usize::from_le_bytes(buf))
+}
+
+/// The pc, then the return addresses along the frame pointer chain, and
+/// whether there were more than [`MAX_FRAMES`].
+fn walk(memory_set: &MemorySet, pc: usize, mut fp: usize) -> (Vec<usize>, bool) {
+    let mut frames = Vec::from([pc]);
+    while fp % 8 == 0 && fp >= 16 {
+        let (ra, caller_fp) = match (
+            read_usize(memory_set, fp - 8),
+            read_usize(memory_set, fp - 16),
+        ) {
+            (Some(ra), Some(caller_fp)) if ra != 0 => (ra, caller_fp),
+            _ => break,
+        };
+        if frames.len() == MAX_FRAMES {
+            return (frames, true);
+        }
+        frames.push(ra);
+        // the stack grows down, callers are further up
+        if caller_fp <= fp {
+            break;
+        }
+        fp = caller_fp;
+    }
+    (frames, false)
+}
+
+/// The function `addr` is in and its start, from the symbols of `image`.
+fn symbolize(image: &'static [u8], addr: usize) -> Option<(&'static str, usize)> {
+    let elf = ElfFile::new(image).ok()?;
+    let symbols = match elf.find_section_by_name(".symtab")?.get_data(&elf).ok()? {
+        SectionData::SymbolTable64(symbols) => symbols,
+        _ => return None,
+    };
+    let symbol = symbols.iter().find(|symbol| {
+        let start = symbol.value() as usize;
+        matches!(symbol.get_type(), Ok(Type::Func))
+            && start <= addr
+            && addr < start + symbol.size() as usize
+    })?;
+    Some((symbol.get_name(&elf).ok()?, symbol.value() as usize))
+}
+
+/// A symbol with the legacy Rust mangling undone, e.g.
+/// `_ZN8user_lib4exit17h0123456789abcdefE` shown as `user_lib::exit`.
+struct Demangle<'a>(&'a str);
+
+impl fmt::Display for Demangle<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rest = match self.0.strip_prefix("_ZN") {
+            Some(rest) if rest.ends_with('E') => rest,
+            _ => return f.write_str(self.0),
+        };
+        let mut first = true;
+        while !rest.starts_with('E') {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            let len = match rest[..digits].parse::<usize>() {
+                Ok(len) if digits + len <= rest.len() => len,
+                _ => return f.write_str(self.0),
+            };
+            let ident = &rest[digits..digits + len];
+            rest = &rest[digits + len..];
+            // the trailing hash tells apart symbols of the same path
+            if rest == "E" && is_hash(ident) {
+                break;
+            }
+            if !first {
+                f.write_str("::")?;
+            }
+            first = false;
+            write_ident(f, ident)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_hash(ident: &str) -> bool {
+    ident.len() == 17
+        && ident
+            .strip_prefix('h')
+            .map_or(false, |hex| hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+}
+
+/// Write `ident` with its `$..$` escapes undone.
+fn write_ident(f: &mut fmt::Formatter, ident: &str) -> fmt::Result {
+    // a leading `$` is escaped with `_`
+    let mut rest = ident
+        .strip_prefix('_')
+        .filter(|rest| rest.starts_with('$'))
+        .unwrap_or(ident);
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix("..") {
+            f.write_str("::")?;
+            rest = tail;
+            continue;
+        }
+        if let Some(tail) = rest.strip_prefix('$') {
+            if let Some(end) = tail.find('$') {
+                let escape = &tail[..end];
+                let unescaped = match escape {
+                    "SP" => Some("@"),
+                    "BP" => Some("*"),
+                    "RF" => Some("&"),
+                    "LT" => Some("<"),
+                    "GT" => Some(">"),
+                    "LP" => Some("("),
+                    "RP" => Some(")"),
+                    "C" => Some(","),
+                    _ => None,
+                };
+                if let Some(unescaped) = unescaped {
+                    f.write_str(unescaped)?;
+                    rest = &tail[end + 1..];
+                    continue;
+                }
+                let code = escape
+                    .strip_prefix('u')
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32);
+                if let Some(c) = code {
+                    write!(f, "{}", c)?;
+                    rest = &tail[end + 1..];
+                    continue;
+                }
+            }
+        }
+        let c = rest.chars().next().unwrap();
+        write!(f, "{}", c)?;
+        rest = &rest[c.len_utf8()..];
+    }
+    Ok(())
+}
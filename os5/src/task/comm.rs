@@ -0,0 +1,76 @@
+//! Process names
+//!
+//! A task is named after the app it was loaded from, by `spawn`, `exec` or
+//! as `INITPROC`, and keeps the name across `fork`. The name can be changed
+//! with `prctl`, and shows up in kernel logs and the process list.
+
+use core::fmt;
+
+/// bytes in a name including the terminating nul, as `TASK_COMM_LEN` in Linux
+pub const COMM_LEN: usize = 16;
+
+/// a nul-terminated name of at most `COMM_LEN - 1` bytes
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Comm([u8; COMM_LEN]);
+
+impl Comm {
+    /// Take the name from the part of `path` after the last `/`, cut to fit.
+    pub fn new(path: &str) -> Self {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        Self::from_bytes(name.as_bytes())
+    }
+    /// Cut `bytes` at the first nul or to `COMM_LEN - 1` bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut comm = [0u8; COMM_LEN];
+        let len = bytes
+            .iter()
+            .take(COMM_LEN - 1)
+            .position(|&ch| ch == 0)
+            .unwrap_or_else(|| bytes.len().min(COMM_LEN - 1));
+        comm[..len].copy_from_slice(&bytes[..len]);
+        Self(comm)
+    }
+    /// the raw name as copied out to user space
+    pub fn as_bytes(&self) -> &[u8; COMM_LEN] {
+        &self.0
+    }
+    pub fn len(&self) -> usize {
+        self.0.iter().position(|&ch| ch == 0).unwrap_or(COMM_LEN)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0[0] == 0
+    }
+}
+
+impl fmt::Display for Comm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // names are set from user memory and need not be ASCII
+        for &ch in &self.0[..self.len()] {
+            if ch.is_ascii_graphic() || ch == b' ' {
+                write!(f, "{}", ch as char)?;
+            } else {
+                write!(f, "\\x{:02x}", ch)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Comm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+/// a task as named in kernel logs, e.g. `ch5b_initproc (pid 0)`
+#[derive(Copy, Clone)]
+pub struct TaskLabel {
+    pub pid: usize,
+    pub comm: Comm,
+}
+
+impl fmt::Display for TaskLabel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (pid {})", self.comm, self.pid)
+    }
+}
@@ -0,0 +1,394 @@
+//! ELF core dumps of processes killed by a fault
+//!
+//! The dump is laid out as Linux does on riscv64, so that `gdb app core`
+//! shows the registers, backtrace and writable memory of the process. A
+//! `PT_NOTE` segment holds the `NT_PRSTATUS`, `NT_PRPSINFO` and `NT_SIGINFO`
+//! notes, along with an `RCORE` note of the raw `scause`, `stval` and
+//! `sepc` and one of the backtrace printed by the kernel. Then comes one `PT_LOAD` segment per user area, of which only the
+//! writable ones carry their contents: code and read-only data are in the
+//! executable.
+//!
+//! os5 has no block device, so a dump is kept in kernel memory. It is handed
+//! to the parent when the process is reaped, which reads it with
+//! `sys_coredump`. All the dumps kept share [`CORE_BUDGET`] bytes of the
+//! kernel heap, a dump which does not fit is skipped.
+
+use super::exit::Fault;
+use super::processor::current_task;
+use super::rlimit::RLIMIT_CORE;
+use super::task::TaskControlBlockInner;
+use super::TaskControlBlock;
+use crate::config::PAGE_SIZE;
+use crate::mm::{MapAreaInfo, MapPermission, PhysAddr, VirtPageNum};
+use crate::timer::get_time_ns;
+use crate::trap::copy_from_nofault;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// the largest dump kept, whatever `RLIMIT_CORE` says, to spare the kernel
+/// heap
+pub const MAX_CORE_SIZE: usize = 512 * 1024;
+/// the bytes all dumps kept may take together, from zombies not reaped yet
+/// to the last one handed to each parent
+pub const CORE_BUDGET: usize = 2 * MAX_CORE_SIZE;
+
+/// bytes taken by the dumps alive, out of [`CORE_BUDGET`]
+static CORE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// A core dump kept in memory, which gives its bytes back to the budget
+/// when dropped. It is moved, never copied, from the zombie to its parent.
+pub struct CoreDump {
+    bytes: Vec<u8>,
+    /// taken out of [`CORE_BUDGET`]
+    charge: usize,
+}
+
+impl CoreDump {
+    /// Room for a dump of `size` bytes, if the budget and the heap allow.
+    fn with_capacity(size: usize) -> Option<Self> {
+        CORE_BYTES
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used + size).filter(|&used| used <= CORE_BUDGET)
+            })
+            .ok()?;
+        let mut bytes = Vec::new();
+        if bytes.try_reserve_exact(size).is_err() {
+            CORE_BYTES.fetch_sub(size, Ordering::Relaxed);
+            return None;
+        }
+        Some(Self {
+            bytes,
+            charge: size,
+        })
+    }
+}
+
+impl Drop for CoreDump {
+    fn drop(&mut self) {
+        CORE_BYTES.fetch_sub(self.charge, Ordering::Relaxed);
+    }
+}
+
+impl Deref for CoreDump {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+const EHDR_SIZE: usize = 64;
+const PHDR_SIZE: usize = 56;
+const ET_CORE: u16 = 4;
+const EM_RISCV: u16 = 243;
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+const NT_PRSTATUS: u32 = 1;
+const NT_PRPSINFO: u32 = 3;
+const NT_SIGINFO: u32 = 0x5349_4749;
+/// the `RCORE` note of `scause`, `stval` and `sepc`
+const NT_RCORE_TRAP: u32 = 1;
+/// the `RCORE` note of the symbolized backtrace, as text
+const NT_RCORE_BACKTRACE: u32 = 2;
+const PRSTATUS_SIZE: usize = 376;
+const PRPSINFO_SIZE: usize = 136;
+const SIGINFO_SIZE: usize = 128;
+const RCORE_TRAP_SIZE: usize = 24;
+
+/// `si_code` for a fault on an unmapped address, or an illegal opcode
+const SI_CODE_FAULT: i32 = 1;
+
+/// the process as seen by the notes
+struct Process<'a> {
+    pid: usize,
+    ppid: usize,
+    signal: i32,
+    fault: Fault,
+    backtrace: &'a str,
+}
+
+/// Dump the core of the current task, killed by `signal`, and keep it for
+/// the parent. `backtrace` is from [`current_backtrace`]. Return false if
+/// the dump would be larger than allowed, or there is no memory left for
+/// it.
+///
+/// [`current_backtrace`]: super::current_backtrace
+pub fn dump_current_core(signal: i32, fault: Fault, backtrace: &str) -> bool {
+    let task = current_task().unwrap();
+    let ppid = parent_pid(&task);
+    let mut inner = task.inner_exclusive_access();
+    let limit = inner.rlimits.cur(RLIMIT_CORE).min(MAX_CORE_SIZE);
+    let process = Process {
+        pid: task.getpid(),
+        ppid,
+        signal,
+        fault,
+        backtrace,
+    };
+    let core = build(&inner, &process, limit);
+    let dumped = core.is_some();
+    inner.core = core;
+    dumped
+}
+
+fn parent_pid(task: &TaskControlBlock) -> usize {
+    let inner = task.inner_exclusive_access();
+    inner
+        .parent
+        .as_ref()
+        .and_then(|parent| parent.upgrade())
+        .map_or(0, |parent| parent.getpid())
+}
+
+fn is_user(area: &MapAreaInfo) -> bool {
+    area.map_perm & MapPermission::U.bits() as usize != 0
+}
+
+fn is_writable(area: &MapAreaInfo) -> bool {
+    area.map_perm & MapPermission::W.bits() as usize != 0
+}
+
+fn flags(area: &MapAreaInfo) -> u32 {
+    let perm = MapPermission::from_bits_truncate(area.map_perm as u8);
+    let mut flags = 0;
+    if perm.contains(MapPermission::R) {
+        flags |= PF_R;
+    }
+    if perm.contains(MapPermission::W) {
+        flags |= PF_W;
+    }
+    if perm.contains(MapPermission::X) {
+        flags |= PF_X;
+    }
+    flags
+}
+
+fn note_size(name: &str, desc_size: usize) -> usize {
+    12 + align4(name.len() + 1) + align4(desc_size)
+}
+
+fn align4(size: usize) -> usize {
+    (size + 3) & !3
+}
+
+fn build(inner: &TaskControlBlockInner, process: &Process, limit: usize) -> Option<CoreDump> {
+    let areas: Vec<MapAreaInfo> = inner
+        .memory_set
+        .areas_info()
+        .into_iter()
+        .filter(is_user)
+        .collect();
+    let notes_size = note_size("CORE", PRSTATUS_SIZE)
+        + note_size("CORE", PRPSINFO_SIZE)
+        + note_size("CORE", SIGINFO_SIZE)
+        + note_size("RCORE", RCORE_TRAP_SIZE)
+        + note_size("RCORE", process.backtrace.len());
+    let notes_offset = EHDR_SIZE + PHDR_SIZE * (areas.len() + 1);
+    let data_offset = (notes_offset + notes_size + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+    let data_size: usize = areas
+        .iter()
+        .filter(|area| is_writable(area))
+        .map(|area| (area.end_vpn - area.start_vpn) * PAGE_SIZE)
+        .sum();
+    let total = data_offset + data_size;
+    if total > limit {
+        return None;
+    }
+
+    let mut dump = CoreDump::with_capacity(total)?;
+    let mut core = CoreWriter(mem::take(&mut dump.bytes));
+    core.elf_header(areas.len() + 1);
+    core.program_header(PT_NOTE, 0, notes_offset, 0, notes_size, 0);
+    let mut offset = data_offset;
+    for area in areas.iter() {
+        let size = (area.end_vpn - area.start_vpn) * PAGE_SIZE;
+        let file_size = if is_writable(area) { size } else { 0 };
+        core.program_header(
+            PT_LOAD,
+            flags(area),
+            offset,
+            area.start_vpn * PAGE_SIZE,
+            file_size,
+            size,
+        );
+        offset += file_size;
+    }
+    core.prstatus(inner, process);
+    core.prpsinfo(inner, process);
+    core.siginfo(process);
+    core.trap_note(process);
+    core.backtrace_note(process);
+    core.pad_to(data_offset);
+    for area in areas.iter().filter(|area| is_writable(area)) {
+        for vpn in area.start_vpn..area.end_vpn {
+            match inner.memory_set.translate(VirtPageNum(vpn)) {
+                Some(pte) if pte.is_valid() => {
+                    let start = core.0.len();
+                    core.0.resize(start + PAGE_SIZE, 0);
+                    // a poisoned page reads as zeros too
+                    let src: PhysAddr = pte.ppn().into();
+                    if !copy_from_nofault(&mut core.0[start..], src.0) {
+                        core.0[start..].fill(0);
+                    }
+                }
+                // not backed by a frame yet, reads as zeros
+                _ => core.0.resize(core.0.len() + PAGE_SIZE, 0),
+            }
+        }
+    }
+    debug_assert_eq!(core.0.len(), total);
+    dump.bytes = core.0;
+    Some(dump)
+}
+
+struct CoreWriter(Vec<u8>);
+
+impl CoreWriter {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn pad_to(&mut self, len: usize) {
+        self.0.resize(len, 0);
+    }
+    fn elf_header(&mut self, phnum: usize) {
+        // ELFCLASS64, ELFDATA2LSB, EV_CURRENT, ELFOSABI_NONE
+        self.0.extend_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0]);
+        self.pad_to(16);
+        self.u16(ET_CORE);
+        self.u16(EM_RISCV);
+        self.u32(1);
+        // no entry point and no section headers
+        self.u64(0);
+        self.u64(EHDR_SIZE as u64);
+        self.u64(0);
+        // EF_RISCV_FLOAT_ABI_DOUBLE | EF_RISCV_RVC, as for the apps
+        self.u32(0x5);
+        self.u16(EHDR_SIZE as u16);
+        self.u16(PHDR_SIZE as u16);
+        self.u16(phnum as u16);
+        self.u16(0);
+        self.u16(0);
+        self.u16(0);
+    }
+    fn program_header(
+        &mut self,
+        kind: u32,
+        flags: u32,
+        offset: usize,
+        vaddr: usize,
+        file_size: usize,
+        mem_size: usize,
+    ) {
+        self.u32(kind);
+        self.u32(flags);
+        self.u64(offset as u64);
+        self.u64(vaddr as u64);
+        self.u64(0);
+        self.u64(file_size as u64);
+        self.u64(mem_size as u64);
+        self.u64(if kind == PT_LOAD { PAGE_SIZE as u64 } else { 4 });
+    }
+    fn note_header(&mut self, name: &str, kind: u32, desc_size: usize) {
+        self.u32(name.len() as u32 + 1);
+        self.u32(desc_size as u32);
+        self.u32(kind);
+        self.0.extend_from_slice(name.as_bytes());
+        let end = self.0.len() + align4(name.len() + 1) - name.len();
+        self.pad_to(end);
+    }
+    fn timeval(&mut self, ns: usize) {
+        self.u64((ns / 1_000_000_000) as u64);
+        self.u64((ns % 1_000_000_000 / 1000) as u64);
+    }
+    fn ids(&mut self, inner: &TaskControlBlockInner, process: &Process) {
+        self.u32(process.pid as u32);
+        self.u32(process.ppid as u32);
+        self.u32(inner.pgid as u32);
+        self.u32(inner.sid as u32);
+    }
+    fn prstatus(&mut self, inner: &TaskControlBlockInner, process: &Process) {
+        self.note_header("CORE", NT_PRSTATUS, PRSTATUS_SIZE);
+        let start = self.0.len();
+        // si_signo, si_code, si_errno, then pr_cursig
+        self.u32(process.signal as u32);
+        self.u32(0);
+        self.u32(0);
+        self.u16(process.signal as u16);
+        self.u16(0);
+        // pr_sigpend, pr_sighold
+        self.u64(0);
+        self.u64(0);
+        self.ids(inner, process);
+        let usage = inner.accounting.current(get_time_ns());
+        self.timeval(usage.user_ns);
+        self.timeval(usage.system_ns);
+        self.timeval(inner.children_usage.user_ns);
+        self.timeval(inner.children_usage.system_ns);
+        // pr_reg is `struct user_regs_struct`: pc, then x1 to x31
+        let trap_cx = inner.get_trap_cx();
+        self.u64(trap_cx.sepc as u64);
+        for reg in trap_cx.x.iter().skip(1) {
+            self.u64(*reg as u64);
+        }
+        // pr_fpvalid
+        self.u32(0);
+        self.pad_to(start + PRSTATUS_SIZE);
+    }
+    fn prpsinfo(&mut self, inner: &TaskControlBlockInner, process: &Process) {
+        self.note_header("CORE", NT_PRPSINFO, PRPSINFO_SIZE);
+        let start = self.0.len();
+        // pr_state, pr_sname, pr_zomb, pr_nice
+        self.u8(0);
+        self.u8(b'R');
+        self.u8(0);
+        self.u8(0);
+        self.pad_to(start + 8);
+        // pr_flag, pr_uid, pr_gid
+        self.u64(0);
+        self.u32(0);
+        self.u32(0);
+        self.ids(inner, process);
+        // pr_fname, then pr_psargs as there are no arguments
+        self.0.extend_from_slice(inner.comm.as_bytes());
+        self.0.extend_from_slice(inner.comm.as_bytes());
+        self.pad_to(start + PRPSINFO_SIZE);
+    }
+    fn siginfo(&mut self, process: &Process) {
+        self.note_header("CORE", NT_SIGINFO, SIGINFO_SIZE);
+        let start = self.0.len();
+        self.u32(process.signal as u32);
+        self.u32(0);
+        self.u32(SI_CODE_FAULT as u32);
+        self.u32(0);
+        // si_addr
+        self.u64(process.fault.addr as u64);
+        self.pad_to(start + SIGINFO_SIZE);
+    }
+    fn trap_note(&mut self, process: &Process) {
+        self.note_header("RCORE", NT_RCORE_TRAP, RCORE_TRAP_SIZE);
+        self.u64(process.fault.cause as u64);
+        self.u64(process.fault.addr as u64);
+        self.u64(process.fault.pc as u64);
+    }
+    fn backtrace_note(&mut self, process: &Process) {
+        let size = process.backtrace.len();
+        self.note_header("RCORE", NT_RCORE_BACKTRACE, size);
+        let end = self.0.len() + align4(size);
+        self.0.extend_from_slice(process.backtrace.as_bytes());
+        self.pad_to(end);
+    }
+}
@@ -0,0 +1,94 @@
+//! How a task ended
+//!
+//! A [`Termination`] is reported to the parent in two forms. The exit code
+//! is the value given to `exit`, or a negative number for a task the kernel
+//! killed, which is what the user programs shared by every chapter check.
+//! The wait status is encoded as in POSIX, so that a fault can be told apart
+//! from a task which called `exit(-2)`.
+
+/// signals, only ever used to say why the kernel killed a task
+pub const SIGILL: i32 = 4;
+pub const SIGTRAP: i32 = 5;
+pub const SIGKILL: i32 = 9;
+pub const SIGSEGV: i32 = 11;
+pub const SIGXCPU: i32 = 24;
+pub const SIGSYS: i32 = 31;
+
+/// the exit code of a task killed by the OOM killer, minus `ENOMEM`, so that
+/// it is told apart from the -9 of any other `SIGKILL`
+pub const OOM_EXIT_CODE: i32 = -12;
+
+/// set in the wait status if a core dump was written
+const WCOREFLAG: i32 = 0x80;
+/// the low byte of the wait status of a stopped task
+const WSTOPFLAG: i32 = 0x7f;
+
+/// the wait status of a task stopped by `signal`, for its tracer
+pub fn stop_status(signal: i32) -> i32 {
+    signal << 8 | WSTOPFLAG
+}
+
+/// the trap which killed a task
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Fault {
+    /// `scause`
+    pub cause: usize,
+    /// `stval`, the faulting address for page and access faults
+    pub addr: usize,
+    /// `sepc`
+    pub pc: usize,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Termination {
+    /// by `exit` with this code
+    Exited(i32),
+    /// by the kernel, as if by this signal
+    Killed(i32),
+    /// by the OOM killer, as if by `SIGKILL` but with [`OOM_EXIT_CODE`]
+    OutOfMemory,
+    /// by a fatal trap, reported as `signal`
+    Faulted {
+        signal: i32,
+        fault: Fault,
+        core_dumped: bool,
+    },
+}
+
+impl Termination {
+    /// the POSIX wait status, as for `WIFEXITED` and friends
+    pub fn status(&self) -> i32 {
+        match *self {
+            Termination::Exited(code) => (code & 0xff) << 8,
+            Termination::Killed(signal) => signal,
+            Termination::OutOfMemory => SIGKILL,
+            Termination::Faulted {
+                signal,
+                core_dumped,
+                ..
+            } => signal | if core_dumped { WCOREFLAG } else { 0 },
+        }
+    }
+    /// the exit code as reported before wait statuses, -2 and -3 for page
+    /// faults and illegal instructions, [`OOM_EXIT_CODE`] for the OOM killer
+    /// and minus the signal otherwise
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            Termination::Exited(code) => code,
+            Termination::Faulted {
+                signal: SIGSEGV, ..
+            } => -2,
+            Termination::Faulted {
+                signal: SIGILL, ..
+            } => -3,
+            Termination::OutOfMemory => OOM_EXIT_CODE,
+            Termination::Killed(signal) | Termination::Faulted { signal, .. } => -signal,
+        }
+    }
+    pub fn fault(&self) -> Option<Fault> {
+        match *self {
+            Termination::Faulted { fault, .. } => Some(fault),
+            _ => None,
+        }
+    }
+}
@@ -1,59 +1,125 @@
+mod backtrace;
+mod comm;
 mod context;
+mod coredump;
+mod exit;
 mod switch;
 mod pid;
 mod manager;
 mod processor;
+mod ptrace;
+mod oom;
+mod rlimit;
+mod rusage;
+mod seccomp;
+mod trace;
 #[allow(clippy::module_inception)]
 mod task;
 
 pub use switch::__switch;
 pub use manager::{fetch_task, add_task};
 pub use task::{TaskControlBlock, TaskStatus};
-pub use pid::{PidHandle, KernelStack, pid_alloc};
+pub use pid::{PidHandle, KernelStack, pid_alloc, register_task};
+pub use comm::{Comm, COMM_LEN};
 pub use context::TaskContext;
-pub use processor::{current_user_token, current_trap_cx, run_tasks, current_task, 
+pub use backtrace::{current_backtrace, print_current_backtrace};
+pub use coredump::dump_current_core;
+pub use exit::{stop_status, Fault, Termination, SIGILL, SIGKILL, SIGSEGV, SIGTRAP};
+pub use oom::oom_kill;
+pub use ptrace::{
+    current_is_traced, handle_pending_stop, read_user, resume, stop_current_and_run_next,
+    write_user, Tracee, SIGSTOP,
+};
+pub use rlimit::{RLimit, CPU_LIMIT_KILLED};
+pub use trace::{SyscallTrace, TraceEntry};
+pub use rusage::Usage;
+pub use seccomp::{
+    ArgOp, ArgPredicate, FilterAction, SyscallFilter, FILTER_WORDS, MAX_ARG_PREDICATES,
+    SECCOMP_KILLED,
+};
+pub use processor::{current_user_token, current_trap_cx, current_error_abi, run_tasks, current_task, 
     take_current_task, schedule, increase_current_task_syscall,get_current_task_info,
-    current_mmap, current_munmap
+    current_mmap, current_munmap, charge_current_tick, trace_syscall_begin, trace_syscall_end,
+    check_current_syscall, current_task_label, try_current_task_label, account_trap_entry, account_trap_return, count_current_page_fault
 };
 
 use crate::loader::get_app_data_by_name;
+use crate::timer::get_time_ns;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use lazy_static::*;
 
 
 lazy_static! {
-    pub static ref INITPROC: Arc<TaskControlBlock> = Arc::new(
-        TaskControlBlock::new(get_app_data_by_name("ch5b_initproc").unwrap())
-    );
+    pub static ref INITPROC: Arc<TaskControlBlock> = {
+        let initproc = Arc::new(
+            TaskControlBlock::new(
+                "ch5b_initproc",
+                get_app_data_by_name("ch5b_initproc").unwrap(),
+            )
+        );
+        register_task(&initproc);
+        initproc
+    };
 }
 
 pub fn add_initproc() {
     add_task(INITPROC.clone());
 }
 
+/// Collect every task that has not been reaped yet, in order of pid.
+pub fn all_tasks() -> Vec<Arc<TaskControlBlock>> {
+    pid::registered_tasks()
+}
+
+/// Look up a live task by pid.
+pub fn find_task(pid: usize) -> Option<Arc<TaskControlBlock>> {
+    pid::task_by_pid(pid)
+}
+
+/// Give up the CPU, e.g. to yield or to wait for something.
 pub fn suspend_current_and_run_next() {
+    suspend_and_run_next(true);
+}
+
+/// Take the CPU away from the current task at a timer tick.
+pub fn preempt_current_and_run_next() {
+    suspend_and_run_next(false);
+}
+
+fn suspend_and_run_next(voluntary: bool) {
     let task = take_current_task().unwrap();
 
     let mut task_inner = task.inner_exclusive_access();
     let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
 
     task_inner.task_status = TaskStatus::Ready;
+    task_inner.accounting.switch_out(get_time_ns(), voluntary);
     drop(task_inner);
 
     add_task(task);
     schedule(task_cx_ptr);
 }
 
-pub fn exit_current_and_run_next(exit_code: i32) {
+pub fn exit_current_and_run_next(termination: Termination) {
     let task = take_current_task().unwrap();
     let mut inner = task.inner_exclusive_access();
     inner.task_status = TaskStatus::Zombie;
-    inner.exit_code = exit_code;
+    inner.termination = termination;
+    // charge the time spent exiting
+    inner.accounting.leave_kernel(get_time_ns());
 
     {
         let mut initproc_inner = INITPROC.inner_exclusive_access();
         for child in inner.children.iter() {
-            child.inner_exclusive_access().parent = Some(Arc::downgrade(&INITPROC));
+            let mut child_inner = child.inner_exclusive_access();
+            child_inner.parent = Some(Arc::downgrade(&INITPROC));
+            // the tracer is gone, let the child run on untraced
+            if let Some(mut tracee) = child_inner.tracee.take() {
+                tracee.remove_step_breakpoints(&child_inner.memory_set);
+            }
+            drop(child_inner);
+            ptrace::resume(child);
             initproc_inner.children.push(child.clone());
         }
     }
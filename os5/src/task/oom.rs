@@ -0,0 +1,66 @@
+//! Out-of-memory killer
+//!
+//! When an allocation on behalf of a process can't be satisfied, the kernel
+//! reclaims frames by killing the process which holds the most of them
+//! instead of panicking.
+//!
+//! User memory is mapped eagerly, so frames only run out in the syscalls
+//! which map memory: `fork`, `exec`, `spawn`, `mmap` and `ring_setup` call
+//! [`oom_kill`] and try again. Nothing else does, and if the caller is the
+//! worst offender itself, the syscall fails with `ENOMEM` instead.
+
+use super::exit::Termination;
+use super::manager::PRIORITY_INIT;
+use super::ptrace::resume;
+use super::{all_tasks, current_task, suspend_current_and_run_next, TaskControlBlock, INITPROC};
+use alloc::sync::Arc;
+
+/// the end of a process killed by the OOM killer
+const OOM_KILLED: Termination = Termination::OutOfMemory;
+
+/// Resident pages, weighted down for processes of high priority.
+fn badness(task: &Arc<TaskControlBlock>) -> usize {
+    let inner = task.inner_exclusive_access();
+    inner.memory_set.resident_pages() * PRIORITY_INIT / inner.priority
+}
+
+/// Kill the live process with the largest [`badness`] and wait until it has
+/// exited, so that its frames are back in the allocator.
+///
+/// Return false if nothing can be killed, or if the current process is the
+/// worst offender: its own request should fail instead of taking down others.
+pub fn oom_kill() -> bool {
+    let current = current_task().unwrap();
+    let victim = all_tasks()
+        .into_iter()
+        .filter(|task| !Arc::ptr_eq(task, &INITPROC))
+        .filter(|task| {
+            let inner = task.inner_exclusive_access();
+            !inner.is_zombie() && inner.killed.is_none()
+        })
+        .max_by_key(badness);
+    let victim = match victim {
+        Some(victim) if !Arc::ptr_eq(&victim, &current) && badness(&victim) > 0 => victim,
+        _ => return false,
+    };
+    drop(current);
+    println!(
+        "[kernel] Out of memory, killed process {} with {} resident pages.",
+        victim.label(),
+        victim.inner_exclusive_access().memory_set.resident_pages(),
+    );
+    victim.inner_exclusive_access().killed = Some(OOM_KILLED);
+    // a traced victim may be stopped, it has to run to exit
+    resume(&victim);
+    // the victim exits on its way back to user space, and may be reaped
+    // by its parent any time after that
+    let victim = Arc::downgrade(&victim);
+    while let Some(task) = victim.upgrade() {
+        if task.inner_exclusive_access().is_zombie() {
+            break;
+        }
+        drop(task);
+        suspend_current_and_run_next();
+    }
+    true
+}
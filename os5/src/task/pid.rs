@@ -1,5 +1,8 @@
+use super::TaskControlBlock;
 use crate::config::{KERNEL_STACK_SIZE, PAGE_SIZE, TRAMPOLINE};
 use crate::mm::{MapPermission, VirtAddr, KERNEL_SPACE};
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
 use alloc::vec::Vec;
 use crate::sync::UPSafeCell;
 use lazy_static::*;
@@ -8,6 +11,7 @@ pub struct PidHandle(pub usize);
 
 impl Drop for PidHandle {
     fn drop(&mut self) {
+        PID_TABLE.exclusive_access().remove(&self.0);
         PID_ALLOCATOR.exclusive_access().dealloc(self.0);
     }
 }
@@ -50,8 +54,45 @@ lazy_static! {
     };
 }
 
+lazy_static! {
+    /// Every pid in use, pointing at its task once the task is registered.
+    /// Entries go away with the [`PidHandle`], i.e. when a task is reaped.
+    static ref PID_TABLE: UPSafeCell<BTreeMap<usize, Weak<TaskControlBlock>>> = unsafe {
+        UPSafeCell::new(BTreeMap::new())
+    };
+}
+
 pub fn pid_alloc() -> PidHandle {
-    PID_ALLOCATOR.exclusive_access().alloc()
+    let pid_handle = PID_ALLOCATOR.exclusive_access().alloc();
+    PID_TABLE.exclusive_access().insert(pid_handle.0, Weak::new());
+    pid_handle
+}
+
+/// Make a newly built task reachable through its pid.
+pub fn register_task(task: &Arc<TaskControlBlock>) {
+    PID_TABLE
+        .exclusive_access()
+        .insert(task.getpid(), Arc::downgrade(task));
+}
+
+/// Look up a task which has not been reaped yet by pid.
+pub fn task_by_pid(pid: usize) -> Option<Arc<TaskControlBlock>> {
+    PID_TABLE.exclusive_access().get(&pid)?.upgrade()
+}
+
+/// The number of processes which have not been reaped yet: one more with
+/// each pid handed out, one less when it is freed again.
+pub fn process_count() -> usize {
+    PID_TABLE.exclusive_access().len()
+}
+
+/// Every task which has not been reaped yet, in order of pid.
+pub fn registered_tasks() -> Vec<Arc<TaskControlBlock>> {
+    PID_TABLE
+        .exclusive_access()
+        .values()
+        .filter_map(|task| task.upgrade())
+        .collect()
 }
 
 
@@ -66,7 +107,8 @@ pub fn kernel_stack_position(app_id: usize) -> (usize, usize) {
 }
 
 impl KernelStack {
-    pub fn new(pid_handle: &PidHandle) -> Self {
+    /// Return `None` if there are not enough frames for the stack.
+    pub fn new(pid_handle: &PidHandle) -> Option<Self> {
         let pid = pid_handle.0;
         let (kernel_stack_bottom, kernel_stack_top) = kernel_stack_position(pid);
         KERNEL_SPACE
@@ -75,10 +117,10 @@ impl KernelStack {
                 kernel_stack_bottom.into(),
                 kernel_stack_top.into(),
                 MapPermission::R | MapPermission::W,
-            );
-        KernelStack {
+            )?;
+        Some(KernelStack {
             pid: pid_handle.0,
-        }
+        })
     }
 
     pub fn push_on_top<T>(&self, value:T) -> *mut T where
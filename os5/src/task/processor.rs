@@ -3,9 +3,11 @@ use alloc::sync::Arc;
 use super::{TaskContext, TaskControlBlock, __switch, fetch_task, TaskStatus};
 use crate::trap::TrapContext;
 use crate::sync::UPSafeCell;
-use crate::mm::{VirtAddr, MapPermission};
-use crate::syscall::TaskInfo;
-use crate::timer::get_time_us;
+use super::comm::TaskLabel;
+use super::seccomp::FilterAction;
+use crate::mm::{MapError, MapPermission, VirtAddr};
+use crate::syscall::{ErrorAbi, TaskInfo};
+use crate::timer::{get_time_ns, get_time_us};
 use lazy_static::*;
 
 lazy_static! {
@@ -51,29 +53,106 @@ pub fn current_user_token() -> usize {
     token
 }
 
+/// how the current program wants failed syscalls reported
+pub fn current_error_abi() -> ErrorAbi {
+    current_task().unwrap().inner_exclusive_access().error_abi
+}
+
 pub fn current_trap_cx() -> &'static mut TrapContext {
     current_task().unwrap().inner_exclusive_access().get_trap_cx()
 }
 
+/// Name the current task in kernel logs.
+pub fn current_task_label() -> TaskLabel {
+    current_task().unwrap().label()
+}
+
+/// The label of the current task if there is one, without panicking when
+/// the kernel code a trap interrupted had borrowed it.
+pub fn try_current_task_label() -> Option<TaskLabel> {
+    let task = PROCESSOR.try_exclusive_access()?.current()?;
+    let comm = task.try_inner_exclusive_access()?.comm;
+    Some(TaskLabel {
+        pid: task.getpid(),
+        comm,
+    })
+}
+
 
 
-pub fn current_mmap(start: VirtAddr, len: usize, perm: MapPermission) -> isize {
-    current_task().unwrap().inner_exclusive_access().memory_set.mmap(start, len, perm)
+pub fn current_mmap(
+    start: VirtAddr,
+    len: usize,
+    perm: MapPermission,
+    huge: bool,
+) -> Result<(), MapError> {
+    current_task().unwrap().inner_exclusive_access().memory_set.mmap(start, len, perm, huge)
 }
 
-pub fn current_munmap(start: VirtAddr, len: usize) -> isize {
+pub fn current_munmap(start: VirtAddr, len: usize) -> Result<(), MapError> {
     current_task().unwrap().inner_exclusive_access().memory_set.munmap(start, len)
 }
 
 
-pub fn get_current_task_info(ti: *mut TaskInfo) -> isize {
-    current_task().unwrap().inner_exclusive_access().get_task_info(ti)
+pub fn get_current_task_info() -> TaskInfo {
+    current_task().unwrap().inner_exclusive_access().get_task_info()
 }
 
 pub fn increase_current_task_syscall(syscall_id: usize) {
     current_task().unwrap().inner_exclusive_access().increase_task_syscall(syscall_id);
 }
 
+/// Check a syscall against the filter of the current task, if any.
+pub fn check_current_syscall(syscall_id: usize, args: &[usize; 6]) -> Result<(), FilterAction> {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    match inner.filter.as_ref() {
+        Some(filter) => filter.check(syscall_id, args),
+        None => Ok(()),
+    }
+}
+
+/// Charge the time since the current task returned to user space as user
+/// time, on entry to `trap_handler`.
+pub fn account_trap_entry() {
+    let task = current_task().unwrap();
+    task.inner_exclusive_access().accounting.enter_kernel(get_time_ns());
+}
+
+/// Charge the time since the current task trapped or was switched to as
+/// system time, before returning to user space.
+pub fn account_trap_return() {
+    let task = current_task().unwrap();
+    task.inner_exclusive_access().accounting.leave_kernel(get_time_ns());
+}
+
+pub fn count_current_page_fault() {
+    let task = current_task().unwrap();
+    task.inner_exclusive_access().accounting.usage.page_faults += 1;
+}
+
+/// Log a syscall of the current task if it is being traced.
+pub fn trace_syscall_begin(syscall_id: usize, args: [usize; 6]) {
+    let task = current_task().unwrap();
+    if let Some(trace) = task.inner_exclusive_access().trace.as_mut() {
+        trace.begin(syscall_id, args, get_time_us());
+    }
+}
+
+/// Record the return value of the syscall logged by [`trace_syscall_begin`].
+pub fn trace_syscall_end(ret: isize) {
+    let task = current_task().unwrap();
+    if let Some(trace) = task.inner_exclusive_access().trace.as_mut() {
+        trace.end(ret, get_time_us());
+    }
+}
+
+/// Charge a timer tick to the current task, return false once it has used
+/// up its CPU time limit.
+pub fn charge_current_tick() -> bool {
+    current_task().unwrap().inner_exclusive_access().charge_tick()
+}
+
 
 
 pub fn run_tasks() {
@@ -89,6 +168,7 @@ pub fn run_tasks() {
             if task_inner.start_time == 0 {
                 task_inner.start_time = get_time_us();
             }
+            task_inner.accounting.switch_in(get_time_ns());
             drop(task_inner);
             processor.current = Some(task);
 
@@ -0,0 +1,277 @@
+//! Process tracing for debuggers, in the spirit of ptrace
+//!
+//! A parent may trace a child, which then stops instead of running on when
+//! it hits an `ebreak`, finishes a single step, enters a new image with
+//! `exec` or is asked to stop. A stopped task is off the ready queue until
+//! the tracer resumes it, and each stop is reported once by `waitpid`.
+//!
+//! RISC-V offers no single-step trap outside debug mode, so a step places
+//! `c.ebreak` at every address the current instruction may continue at and
+//! takes them out again at the next stop.
+
+use super::exit::SIGTRAP;
+use super::manager::add_task;
+use super::processor::{current_task, schedule, take_current_task};
+use super::{TaskContext, TaskControlBlock, TaskStatus};
+use crate::mm::{MemorySet, PTEFlags, PhysAddr, VirtAddr};
+use crate::timer::get_time_ns;
+use crate::trap::{copy_from_nofault, copy_to_nofault};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// stop signal of a task asked to stop by its tracer
+pub const SIGSTOP: i32 = 19;
+
+/// `c.ebreak`, two bytes so that it fits over any instruction
+const C_EBREAK: u16 = 0x9002;
+
+/// the tracing state of a traced task
+#[derive(Default)]
+pub struct Tracee {
+    /// stop at the next return to user space
+    pub pending_stop: Option<i32>,
+    /// signal of the current stop, if stopped
+    pub stop_signal: Option<i32>,
+    /// whether `waitpid` has reported the current stop
+    pub reported: bool,
+    /// addresses and replaced halfwords of the `c.ebreak`s of a single step
+    step_breakpoints: Vec<(usize, u16)>,
+}
+
+/// Stop the current task, which is traced, with `signal` and run others
+/// until the tracer resumes it.
+pub fn stop_current_and_run_next(signal: i32) {
+    let task = take_current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let inner = &mut *task_inner;
+    let task_cx_ptr = &mut inner.task_cx as *mut TaskContext;
+    inner.task_status = TaskStatus::Stopped;
+    inner.accounting.switch_out(get_time_ns(), true);
+    if let Some(tracee) = inner.tracee.as_mut() {
+        tracee.remove_step_breakpoints(&inner.memory_set);
+        tracee.stop_signal = Some(signal);
+        tracee.reported = false;
+    }
+    drop(task_inner);
+    // the parent keeps the task alive through its children
+    drop(task);
+    schedule(task_cx_ptr);
+}
+
+/// Stop the current task if its tracer asked for it, unless it is about to
+/// be killed anyway.
+pub fn handle_pending_stop() {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let killed = inner.killed.is_some();
+    let pending = match inner.tracee.as_mut() {
+        Some(tracee) if !killed => tracee.pending_stop.take(),
+        _ => None,
+    };
+    drop(inner);
+    drop(task);
+    if let Some(signal) = pending {
+        stop_current_and_run_next(signal);
+    }
+}
+
+/// Whether the current task has a tracer to report its breakpoints to.
+pub fn current_is_traced() -> bool {
+    current_task().unwrap().inner_exclusive_access().tracee.is_some()
+}
+
+/// Put a stopped task back on the ready queue.
+pub fn resume(task: &Arc<TaskControlBlock>) {
+    let mut inner = task.inner_exclusive_access();
+    if inner.task_status != TaskStatus::Stopped {
+        return;
+    }
+    inner.task_status = TaskStatus::Ready;
+    if let Some(tracee) = inner.tracee.as_mut() {
+        tracee.stop_signal = None;
+    }
+    drop(inner);
+    add_task(task.clone());
+}
+
+impl Tracee {
+    /// The old image is gone along with any single step breakpoints; stop
+    /// before running the new one.
+    /// The signal of the current stop if `waitpid` has not reported it yet,
+    /// which it now has.
+    pub fn take_unreported_stop(&mut self) -> Option<i32> {
+        let signal = self.stop_signal.filter(|_| !self.reported)?;
+        self.reported = true;
+        Some(signal)
+    }
+    pub fn exec(&mut self) {
+        self.step_breakpoints.clear();
+        self.pending_stop = Some(SIGTRAP);
+    }
+    /// Break at every address the instruction at `pc` may continue at.
+    pub fn add_step_breakpoints(
+        &mut self,
+        memory_set: &MemorySet,
+        pc: usize,
+        regs: &[usize; 32],
+    ) -> Option<()> {
+        let low = read_halfword(memory_set, pc)? as u32;
+        let inst = if low & 0b11 == 0b11 {
+            low | (read_halfword(memory_set, pc + 2)? as u32) << 16
+        } else {
+            low
+        };
+        for target in next_pcs(inst, pc, regs).iter().flatten() {
+            if self.step_breakpoints.iter().any(|(addr, _)| addr == target) {
+                continue;
+            }
+            let old = read_halfword(memory_set, *target)?;
+            write_halfword(memory_set, *target, C_EBREAK)?;
+            self.step_breakpoints.push((*target, old));
+        }
+        Some(())
+    }
+    /// Put back what the single step breakpoints replaced.
+    pub fn remove_step_breakpoints(&mut self, memory_set: &MemorySet) {
+        for (addr, old) in self.step_breakpoints.drain(..).rev() {
+            write_halfword(memory_set, addr, old);
+        }
+    }
+}
+
+/// The kernel address of the byte at `va` in user memory. To `write` it, the
+/// kernel ignores the permission of the page, as a debugger sets breakpoints
+/// in code, but the page has to belong to `memory_set` alone: the time page
+/// is shared by every process. A poisoned page is still found here, and only
+/// faults when accessed.
+fn user_byte(memory_set: &MemorySet, va: usize, write: bool) -> Option<usize> {
+    let va = VirtAddr::from(va);
+    let pte = memory_set.translate(va.floor())?;
+    if !pte.is_valid() || !pte.flags().contains(PTEFlags::U) {
+        return None;
+    }
+    if write && !memory_set.is_private(va.floor()) {
+        return None;
+    }
+    let pa: PhysAddr = pte.ppn().into();
+    Some(pa.0 + va.page_offset())
+}
+
+/// Copy `buf.len()` bytes from `va` in `memory_set`.
+pub fn read_user(memory_set: &MemorySet, va: usize, buf: &mut [u8]) -> Option<()> {
+    for (i, byte) in buf.iter_mut().enumerate() {
+        let src = user_byte(memory_set, va.checked_add(i)?, false)?;
+        if !copy_from_nofault(core::slice::from_mut(byte), src) {
+            return None;
+        }
+    }
+    Some(())
+}
+
+/// Copy `buf` to `va` in `memory_set`, all or nothing, and make the change
+/// visible to instruction fetch.
+pub fn write_user(memory_set: &MemorySet, va: usize, buf: &[u8]) -> Option<()> {
+    let dsts: Vec<usize> = (0..buf.len())
+        .map(|i| user_byte(memory_set, va.checked_add(i)?, true))
+        .collect::<Option<_>>()?;
+    // read every byte first, so that a poisoned page fails the write whole
+    let mut old = 0u8;
+    if !dsts
+        .iter()
+        .all(|&dst| copy_from_nofault(core::slice::from_mut(&mut old), dst))
+    {
+        return None;
+    }
+    for (&dst, value) in dsts.iter().zip(buf) {
+        copy_to_nofault(dst, core::slice::from_ref(value));
+    }
+    unsafe {
+        core::arch::asm!("fence.i");
+    }
+    Some(())
+}
+
+fn read_halfword(memory_set: &MemorySet, va: usize) -> Option<u16> {
+    let mut buf = [0u8; 2];
+    read_user(memory_set, va, &mut buf)?;
+    Some(u16::from_le_bytes(buf))
+}
+
+fn write_halfword(memory_set: &MemorySet, va: usize, value: u16) -> Option<()> {
+    write_user(memory_set, va, &value.to_le_bytes())
+}
+
+fn bits(inst: u32, high: u32, low: u32) -> usize {
+    ((inst >> low) & ((1 << (high - low + 1)) - 1)) as usize
+}
+
+/// Sign extend the low `width` bits of `value`.
+fn sign_extend(value: usize, width: u32) -> usize {
+    let shift = usize::BITS - width;
+    (((value << shift) as isize) >> shift) as usize
+}
+
+/// Where execution may continue after the instruction `inst` at `pc`. Both
+/// ways of a branch are given, without evaluating its condition.
+fn next_pcs(inst: u32, pc: usize, regs: &[usize; 32]) -> [Option<usize>; 2] {
+    if inst & 0b11 != 0b11 {
+        return next_pcs_compressed(inst, pc, regs);
+    }
+    let rs1 = bits(inst, 19, 15);
+    match inst & 0x7f {
+        // jal
+        0x6f => {
+            let imm = bits(inst, 31, 31) << 20
+                | bits(inst, 19, 12) << 12
+                | bits(inst, 20, 20) << 11
+                | bits(inst, 30, 21) << 1;
+            [Some(pc.wrapping_add(sign_extend(imm, 21))), None]
+        }
+        // jalr
+        0x67 => {
+            let imm = sign_extend(bits(inst, 31, 20), 12);
+            [Some(regs[rs1].wrapping_add(imm) & !1), None]
+        }
+        // branches
+        0x63 => {
+            let imm = bits(inst, 31, 31) << 12
+                | bits(inst, 7, 7) << 11
+                | bits(inst, 30, 25) << 5
+                | bits(inst, 11, 8) << 1;
+            [Some(pc.wrapping_add(sign_extend(imm, 13))), Some(pc + 4)]
+        }
+        _ => [Some(pc + 4), None],
+    }
+}
+
+fn next_pcs_compressed(inst: u32, pc: usize, regs: &[usize; 32]) -> [Option<usize>; 2] {
+    let funct3 = bits(inst, 15, 13);
+    match (inst & 0b11, funct3) {
+        // c.j
+        (0b01, 0b101) => {
+            let imm = bits(inst, 12, 12) << 11
+                | bits(inst, 8, 8) << 10
+                | bits(inst, 10, 9) << 8
+                | bits(inst, 6, 6) << 7
+                | bits(inst, 7, 7) << 6
+                | bits(inst, 2, 2) << 5
+                | bits(inst, 11, 11) << 4
+                | bits(inst, 5, 3) << 1;
+            [Some(pc.wrapping_add(sign_extend(imm, 12))), None]
+        }
+        // c.beqz, c.bnez
+        (0b01, 0b110) | (0b01, 0b111) => {
+            let imm = bits(inst, 12, 12) << 8
+                | bits(inst, 6, 5) << 6
+                | bits(inst, 2, 2) << 5
+                | bits(inst, 11, 10) << 3
+                | bits(inst, 4, 3) << 1;
+            [Some(pc.wrapping_add(sign_extend(imm, 9))), Some(pc + 2)]
+        }
+        // c.jr, c.jalr
+        (0b10, 0b100) if bits(inst, 6, 2) == 0 && bits(inst, 11, 7) != 0 => {
+            [Some(regs[bits(inst, 11, 7)] & !1), None]
+        }
+        _ => [Some(pc + 2), None],
+    }
+}
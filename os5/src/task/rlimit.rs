@@ -0,0 +1,84 @@
+//! Per-process resource limits
+//!
+//! Each process carries a soft (`cur`) and a hard (`max`) limit for every
+//! resource, inherited by the children created through `fork` and `spawn`.
+//! Resource numbers follow Linux; only the ones below are enforced.
+
+use super::exit::{Termination, SIGXCPU};
+use crate::config::PAGE_SIZE;
+use crate::syscall::Errno;
+
+/// CPU time in seconds, the task is killed once it runs out
+pub const RLIMIT_CPU: usize = 0;
+/// size of the user stack mapped by `exec`/`spawn`, in bytes
+pub const RLIMIT_STACK: usize = 3;
+/// size of a core dump in bytes, none is written if it would be larger
+pub const RLIMIT_CORE: usize = 4;
+/// resident memory in bytes
+pub const RLIMIT_RSS: usize = 5;
+/// number of processes in the system which have not been reaped yet
+pub const RLIMIT_NPROC: usize = 6;
+/// size of the address space in bytes
+pub const RLIMIT_AS: usize = 9;
+pub const RLIM_NLIMITS: usize = 10;
+pub const RLIM_INFINITY: usize = usize::MAX;
+
+/// the end of a process out of CPU time
+pub const CPU_LIMIT_KILLED: Termination = Termination::Killed(SIGXCPU);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct RLimit {
+    pub cur: usize,
+    pub max: usize,
+}
+
+#[derive(Copy, Clone)]
+pub struct RLimits([RLimit; RLIM_NLIMITS]);
+
+impl RLimits {
+    /// nothing is limited
+    pub fn new() -> Self {
+        RLimits(
+            [RLimit {
+                cur: RLIM_INFINITY,
+                max: RLIM_INFINITY,
+            }; RLIM_NLIMITS],
+        )
+    }
+    pub fn get(&self, resource: usize) -> Option<RLimit> {
+        if is_supported(resource) {
+            Some(self.0[resource])
+        } else {
+            None
+        }
+    }
+    /// The soft limit may move freely below the hard one, which can only be
+    /// lowered.
+    pub fn set(&mut self, resource: usize, limit: RLimit) -> Result<(), Errno> {
+        let old = self.get(resource).ok_or(Errno::EINVAL)?;
+        if limit.cur > limit.max {
+            return Err(Errno::EINVAL);
+        }
+        if limit.max > old.max {
+            return Err(Errno::EPERM);
+        }
+        self.0[resource] = limit;
+        Ok(())
+    }
+    pub fn cur(&self, resource: usize) -> usize {
+        self.0[resource].cur
+    }
+    /// Size of the user stack to map, at most `default`, page aligned and
+    /// at least a page, as a program can't start without a stack.
+    pub fn stack_size(&self, default: usize) -> usize {
+        (self.cur(RLIMIT_STACK).min(default) / PAGE_SIZE * PAGE_SIZE).max(PAGE_SIZE)
+    }
+}
+
+fn is_supported(resource: usize) -> bool {
+    matches!(
+        resource,
+        RLIMIT_CPU | RLIMIT_STACK | RLIMIT_CORE | RLIMIT_RSS | RLIMIT_NPROC | RLIMIT_AS
+    )
+}
@@ -0,0 +1,72 @@
+//! Per-task resource usage accounting
+//!
+//! A task is always either in user mode, in the kernel, or switched out.
+//! [`Accounting`] charges the time since the last transition to user or
+//! system time whenever the task traps, returns to user space or gives up
+//! the CPU.
+
+/// resources used by a task, or the sum over several tasks
+#[derive(Copy, Clone, Default)]
+pub struct Usage {
+    pub user_ns: usize,
+    pub system_ns: usize,
+    /// page faults taken, all of them fatal in os5
+    pub page_faults: usize,
+    /// times the task gave up the CPU, by yielding or waiting
+    pub voluntary_switches: usize,
+    /// times the task was preempted by the timer
+    pub involuntary_switches: usize,
+}
+
+impl Usage {
+    pub fn add(&mut self, other: &Usage) {
+        self.user_ns += other.user_ns;
+        self.system_ns += other.system_ns;
+        self.page_faults += other.page_faults;
+        self.voluntary_switches += other.voluntary_switches;
+        self.involuntary_switches += other.involuntary_switches;
+    }
+}
+
+#[derive(Default)]
+pub struct Accounting {
+    pub usage: Usage,
+    /// when the task last trapped, returned to user space or was switched to
+    since_ns: usize,
+}
+
+impl Accounting {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The task has trapped into the kernel.
+    pub fn enter_kernel(&mut self, now_ns: usize) {
+        self.usage.user_ns += now_ns - self.since_ns;
+        self.since_ns = now_ns;
+    }
+    /// The task is about to return to user space.
+    pub fn leave_kernel(&mut self, now_ns: usize) {
+        self.usage.system_ns += now_ns - self.since_ns;
+        self.since_ns = now_ns;
+    }
+    /// The task is switched to, it always resumes in the kernel.
+    pub fn switch_in(&mut self, now_ns: usize) {
+        self.since_ns = now_ns;
+    }
+    /// The task has given up the CPU from within the kernel.
+    pub fn switch_out(&mut self, now_ns: usize, voluntary: bool) {
+        self.usage.system_ns += now_ns - self.since_ns;
+        self.since_ns = now_ns;
+        if voluntary {
+            self.usage.voluntary_switches += 1;
+        } else {
+            self.usage.involuntary_switches += 1;
+        }
+    }
+    /// Usage so far of a task running in the kernel, e.g. handling a syscall.
+    pub fn current(&self, now_ns: usize) -> Usage {
+        let mut usage = self.usage;
+        usage.system_ns += now_ns - self.since_ns;
+        usage
+    }
+}
@@ -0,0 +1,143 @@
+//! Per-process syscall filtering, in the spirit of seccomp
+//!
+//! A [`SyscallFilter`] allows the syscalls whose bit is set in a bitmap and
+//! whose arguments pass every [`ArgPredicate`] given for them. Filters are
+//! inherited by children, and installing another one intersects it with the
+//! current filter, so a sandbox can only ever get tighter. `exit` is always
+//! allowed.
+
+use super::exit::{Termination, SIGSYS};
+use crate::config::MAX_SYSCALL_NUM;
+use alloc::vec::Vec;
+
+/// words of the bitmap covering every syscall id
+pub const FILTER_WORDS: usize = (MAX_SYSCALL_NUM + 63) / 64;
+/// predicates a filter may hold, including those of the filters it was
+/// intersected with
+pub const MAX_ARG_PREDICATES: usize = 32;
+/// the end of a process killed by its filter
+pub const SECCOMP_KILLED: Termination = Termination::Killed(SIGSYS);
+
+const SYSCALL_EXIT: usize = 93;
+
+/// what happens to a syscall the filter rejects
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum FilterAction {
+    /// fail with `EPERM`
+    Errno,
+    /// kill the process
+    Kill,
+}
+
+/// how [`ArgPredicate::value`] is compared with the argument
+#[derive(Copy, Clone)]
+pub enum ArgOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// none of the bits in `value` are set
+    MaskedZero,
+}
+
+impl ArgOp {
+    pub fn from_usize(op: usize) -> Option<Self> {
+        Some(match op {
+            0 => ArgOp::Eq,
+            1 => ArgOp::Ne,
+            2 => ArgOp::Lt,
+            3 => ArgOp::Le,
+            4 => ArgOp::Gt,
+            5 => ArgOp::Ge,
+            6 => ArgOp::MaskedZero,
+            _ => return None,
+        })
+    }
+}
+
+/// `args[arg] op value` must hold for syscall `id`, arguments compared unsigned
+#[derive(Copy, Clone)]
+pub struct ArgPredicate {
+    pub id: usize,
+    pub arg: usize,
+    pub op: ArgOp,
+    pub value: usize,
+}
+
+impl ArgPredicate {
+    fn holds(&self, args: &[usize; 6]) -> bool {
+        let arg = args[self.arg];
+        match self.op {
+            ArgOp::Eq => arg == self.value,
+            ArgOp::Ne => arg != self.value,
+            ArgOp::Lt => arg < self.value,
+            ArgOp::Le => arg <= self.value,
+            ArgOp::Gt => arg > self.value,
+            ArgOp::Ge => arg >= self.value,
+            ArgOp::MaskedZero => arg & self.value == 0,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SyscallFilter {
+    allowed: [u64; FILTER_WORDS],
+    predicates: Vec<ArgPredicate>,
+    action: FilterAction,
+}
+
+impl SyscallFilter {
+    pub fn new(
+        allowed: [u64; FILTER_WORDS],
+        predicates: Vec<ArgPredicate>,
+        action: FilterAction,
+    ) -> Self {
+        Self {
+            allowed,
+            predicates,
+            action,
+        }
+    }
+    pub fn predicate_count(&self) -> usize {
+        self.predicates.len()
+    }
+    /// Return the action to take if the syscall is rejected.
+    pub fn check(&self, id: usize, args: &[usize; 6]) -> Result<(), FilterAction> {
+        if id == SYSCALL_EXIT {
+            return Ok(());
+        }
+        let allowed = id < MAX_SYSCALL_NUM
+            && self.allowed[id / 64] & (1 << (id % 64)) != 0
+            && self
+                .predicates
+                .iter()
+                .filter(|pred| pred.id == id)
+                .all(|pred| pred.holds(args));
+        if allowed {
+            Ok(())
+        } else {
+            Err(self.action)
+        }
+    }
+    /// Allow only what both filters allow, killing if either one kills.
+    pub fn intersect(&self, other: &SyscallFilter) -> SyscallFilter {
+        let mut allowed = self.allowed;
+        for (word, other) in allowed.iter_mut().zip(other.allowed.iter()) {
+            *word &= *other;
+        }
+        let mut predicates = self.predicates.clone();
+        predicates.extend_from_slice(&other.predicates);
+        let action = if self.action == FilterAction::Kill || other.action == FilterAction::Kill {
+            FilterAction::Kill
+        } else {
+            FilterAction::Errno
+        };
+        SyscallFilter {
+            allowed,
+            predicates,
+            action,
+        }
+    }
+}
@@ -1,11 +1,21 @@
-use super::{PidHandle, pid_alloc, KernelStack, TaskContext};
+use super::comm::TaskLabel;
+use super::coredump::CoreDump;
+use super::exit::Termination;
+use super::ptrace::Tracee;
+use super::{Comm, PidHandle, pid_alloc, register_task, KernelStack, TaskContext};
 use super::manager::{PRIORITY_INIT, PASS_INIT, BIG_STRIDE};
-use crate::config::{TRAP_CONTEXT, MAX_SYSCALL_NUM};
+use super::trace::SyscallTrace;
+use super::seccomp::SyscallFilter;
+use super::rusage::{Accounting, Usage};
+use crate::syscall::{ErrorAbi, SyscallRing};
+use super::pid::process_count;
+use super::rlimit::{RLimits, RLIMIT_AS, RLIMIT_CPU, RLIMIT_NPROC, RLIMIT_RSS};
+use crate::config::{TRAP_CONTEXT, MAX_SYSCALL_NUM, PAGE_SIZE, USER_STACK_SIZE};
 use crate::trap::{TrapContext, trap_handler};
 use crate::mm::{PhysPageNum, MemorySet, VirtAddr, KERNEL_SPACE};
 use crate::sync::UPSafeCell;
 use crate::syscall::TaskInfo;
-use crate::timer::get_time_us;
+use crate::timer::{get_time_us, TICKS_PER_SEC};
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use alloc::sync::{Arc, Weak};
@@ -22,18 +32,34 @@ impl TaskControlBlock {
     pub fn inner_exclusive_access(&self) -> RefMut<'_, TaskControlBlockInner> {
         self.inner.exclusive_access()
     }
+    pub fn try_inner_exclusive_access(&self) -> Option<RefMut<'_, TaskControlBlockInner>> {
+        self.inner.try_exclusive_access()
+    }
     pub fn getpid(&self) -> usize {
         self.pid.0
     }
 
-    pub fn new(elf_data: &[u8]) -> Self {
-        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+    /// Return the name of the task, e.g. for logs.
+    pub fn comm(&self) -> Comm {
+        self.inner_exclusive_access().comm
+    }
+    pub fn label(&self) -> TaskLabel {
+        TaskLabel {
+            pid: self.getpid(),
+            comm: self.comm(),
+        }
+    }
+
+    pub fn new(name: &str, elf_data: &'static [u8]) -> Self {
+        let (memory_set, user_sp, entry_point) =
+            MemorySet::from_elf(elf_data, USER_STACK_SIZE).unwrap();
         let trap_cx_ppn = memory_set
             .translate(VirtAddr::from(TRAP_CONTEXT).into())
             .unwrap()
             .ppn();
         let pid_handle = pid_alloc();
-        let kernel_stack = KernelStack::new(&pid_handle);
+        let pid = pid_handle.0;
+        let kernel_stack = KernelStack::new(&pid_handle).unwrap();
         let kernel_stack_top = kernel_stack.get_top();
 
         let task_control_block = Self {
@@ -47,12 +73,28 @@ impl TaskControlBlock {
                 memory_set,
                 parent: None,
                 children: Vec::new(),
-                exit_code: 0,
+                termination: Termination::Exited(0),
                 syscall_times: BTreeMap::new(),
                 start_time: 0,
                 priority: PRIORITY_INIT,
                 pass: PASS_INIT,
                 stride: 0,
+                killed: None,
+                rlimits: RLimits::new(),
+                cpu_ticks: 0,
+                accounting: Accounting::new(),
+                children_usage: Usage::default(),
+                trace: None,
+                filter: None,
+                ring: None,
+                comm: Comm::new(name),
+                image: elf_data,
+                error_abi: ErrorAbi::of(elf_data),
+                pgid: pid,
+                sid: pid,
+                core: None,
+                child_core: None,
+                tracee: None,
             })},
         };
 
@@ -66,8 +108,10 @@ impl TaskControlBlock {
         );
         task_control_block
     }
-    pub fn exec(&self, elf_data: &[u8]) {
-        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+    /// Return `None` and keep the old image if frames run out.
+    pub fn exec(&self, name: &str, elf_data: &'static [u8]) -> Option<()> {
+        let stack_size = self.inner_exclusive_access().rlimits.stack_size(USER_STACK_SIZE);
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data, stack_size)?;
         let trap_cx_ppn = memory_set
             .translate(VirtAddr::from(TRAP_CONTEXT).into())
             .unwrap()
@@ -75,6 +119,13 @@ impl TaskControlBlock {
         let mut inner = self.inner_exclusive_access();
         inner.memory_set = memory_set;
         inner.trap_cx_ppn = trap_cx_ppn;
+        inner.ring = None;
+        inner.comm = Comm::new(name);
+        inner.image = elf_data;
+        inner.error_abi = ErrorAbi::of(elf_data);
+        if let Some(tracee) = inner.tracee.as_mut() {
+            tracee.exec();
+        }
         let trap_cx = inner.get_trap_cx();
         * trap_cx = TrapContext::app_init_context(
             entry_point,
@@ -83,19 +134,21 @@ impl TaskControlBlock {
             self.kernel_stack.get_top(),
             trap_handler as usize,
         );
+        Some(())
     }
-    pub fn fork(self: &Arc<TaskControlBlock>) -> Arc<TaskControlBlock> {
+    /// Return `None` if frames run out.
+    pub fn fork(self: &Arc<TaskControlBlock>) -> Option<Arc<TaskControlBlock>> {
         let mut parent_inner = self.inner_exclusive_access();
         let memory_set = MemorySet::from_existed_user(
             &parent_inner.memory_set
-        );
+        )?;
         let trap_cx_ppn = memory_set
             .translate(VirtAddr::from(TRAP_CONTEXT).into())
             .unwrap()
             .ppn();
         
         let pid_handle = pid_alloc();
-        let kernel_stack = KernelStack::new(&pid_handle);
+        let kernel_stack = KernelStack::new(&pid_handle)?;
         let kernel_stack_top = kernel_stack.get_top();
         let task_control_block = Arc::new(TaskControlBlock {
             pid: pid_handle,
@@ -108,31 +161,54 @@ impl TaskControlBlock {
                 memory_set,
                 parent: Some(Arc::downgrade(self)),
                 children: Vec::new(),
-                exit_code: 0,
+                termination: Termination::Exited(0),
                 syscall_times: parent_inner.syscall_times.clone(),
                 start_time: parent_inner.start_time,
                 priority: parent_inner.priority,
                 pass: parent_inner.pass,
                 stride: parent_inner.stride,
+                killed: None,
+                rlimits: parent_inner.rlimits,
+                cpu_ticks: 0,
+                accounting: Accounting::new(),
+                children_usage: Usage::default(),
+                trace: parent_inner.trace.as_ref().map(|_| SyscallTrace::new()),
+                filter: parent_inner.filter.clone(),
+                ring: parent_inner.ring,
+                comm: parent_inner.comm,
+                image: parent_inner.image,
+                error_abi: parent_inner.error_abi,
+                pgid: parent_inner.pgid,
+                sid: parent_inner.sid,
+                core: None,
+                child_core: None,
+                tracee: None,
             })},
         });
+        register_task(&task_control_block);
         parent_inner.children.push(task_control_block.clone());
 
         let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
         trap_cx.kernel_sp = kernel_stack_top;
         
-        task_control_block
+        Some(task_control_block)
     }
 
-    pub fn spawn(self: &Arc<TaskControlBlock>, elf_data: &[u8]) -> Arc<TaskControlBlock> {
+    /// Return `None` if frames run out.
+    pub fn spawn(
+        self: &Arc<TaskControlBlock>,
+        name: &str,
+        elf_data: &'static [u8],
+    ) -> Option<Arc<TaskControlBlock>> {
         let mut parent_inner = self.inner_exclusive_access();
-        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let stack_size = parent_inner.rlimits.stack_size(USER_STACK_SIZE);
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data, stack_size)?;
         let trap_cx_ppn = memory_set
             .translate(VirtAddr::from(TRAP_CONTEXT).into())
             .unwrap()
             .ppn();
         let pid_handle = pid_alloc();
-        let kernel_stack = KernelStack::new(&pid_handle);
+        let kernel_stack = KernelStack::new(&pid_handle)?;
         let kernel_stack_top = kernel_stack.get_top();
 
         let task_control_block = Arc::new(TaskControlBlock{ 
@@ -146,14 +222,31 @@ impl TaskControlBlock {
                 memory_set,
                 parent: Some(Arc::downgrade(self)),
                 children: Vec::new(),
-                exit_code: 0,
+                termination: Termination::Exited(0),
                 syscall_times: BTreeMap::new(),
                 start_time: 0,
                 priority: PRIORITY_INIT,
                 pass: PASS_INIT,
                 stride: 0,
+                killed: None,
+                rlimits: parent_inner.rlimits,
+                cpu_ticks: 0,
+                accounting: Accounting::new(),
+                children_usage: Usage::default(),
+                trace: parent_inner.trace.as_ref().map(|_| SyscallTrace::new()),
+                filter: parent_inner.filter.clone(),
+                ring: None,
+                comm: Comm::new(name),
+                image: elf_data,
+                error_abi: ErrorAbi::of(elf_data),
+                pgid: parent_inner.pgid,
+                sid: parent_inner.sid,
+                core: None,
+                child_core: None,
+                tracee: None,
             })},
         });
+        register_task(&task_control_block);
         parent_inner.children.push(task_control_block.clone());
         let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
         *trap_cx = TrapContext::app_init_context(
@@ -163,7 +256,7 @@ impl TaskControlBlock {
             kernel_stack_top,
             trap_handler as usize,
         );
-        task_control_block
+        Some(task_control_block)
     }
 }
 
@@ -175,12 +268,44 @@ pub struct TaskControlBlockInner {
     pub memory_set: MemorySet,
     pub parent: Option<Weak<TaskControlBlock>>,
     pub children: Vec<Arc<TaskControlBlock>>,
-    pub exit_code: i32,
+    /// how the task ended, once it is a zombie
+    pub termination: Termination,
     pub syscall_times: BTreeMap<u16, u32>,
     pub start_time: usize,
     pub priority: usize,
     pub pass: usize,
     pub stride: usize,
+    /// chosen by the OOM killer or killed by the tracer, exits this way
+    /// before returning to user space
+    pub killed: Option<Termination>,
+    pub rlimits: RLimits,
+    /// timer ticks spent running in user mode
+    pub cpu_ticks: usize,
+    pub accounting: Accounting,
+    /// usage of the children reaped so far, and of their reaped children
+    pub children_usage: Usage,
+    /// syscalls are logged here while tracing is on
+    pub trace: Option<SyscallTrace>,
+    /// syscalls allowed to the process, everything if `None`
+    pub filter: Option<SyscallFilter>,
+    /// mapped by `sys_ring_setup`, copied along with the memory on `fork`
+    pub ring: Option<SyscallRing>,
+    /// the app name, kept on `fork` and replaced on `exec`
+    pub comm: Comm,
+    /// the ELF the program was loaded from, for its symbols
+    pub image: &'static [u8],
+    /// whether the program wants error numbers, see [`ErrorAbi`]
+    pub error_abi: ErrorAbi,
+    /// process group, inherited on `fork` and `spawn`
+    pub pgid: usize,
+    /// session, inherited on `fork` and `spawn`
+    pub sid: usize,
+    /// dumped when the task was killed by a fault, see [`super::coredump`]
+    pub core: Option<CoreDump>,
+    /// pid and core dump of the child reaped last among those which left one
+    pub child_core: Option<(usize, CoreDump)>,
+    /// set while the parent traces the task, see [`super::ptrace`]
+    pub tracee: Option<Tracee>,
 }
 
 impl TaskControlBlockInner {
@@ -196,27 +321,56 @@ impl TaskControlBlockInner {
     pub fn is_zombie(&self) -> bool {
         self.get_status() == TaskStatus::Zombie
     }
-    pub fn get_task_info(&self, ti: *mut TaskInfo) -> isize {
+    pub fn get_task_info(&self) -> TaskInfo {
         let mut count = [0u32; MAX_SYSCALL_NUM];
         for (key, val) in self.syscall_times.iter() {
             count[*key as usize] = *val;
         }
-        unsafe{
-            *ti = TaskInfo{
-                status: self.task_status,
-                syscall_times: count,
-                time: (get_time_us() - self.start_time) / 1000,
-            }
+        TaskInfo{
+            status: self.task_status,
+            syscall_times: count,
+            time: (get_time_us() - self.start_time) / 1000,
         }
-        0
     }
     pub fn increase_task_syscall(&mut self, syscall_id: usize) {
+        // unknown syscalls fail with ENOSYS and are not counted
+        if syscall_id >= MAX_SYSCALL_NUM {
+            return;
+        }
         let count = self.syscall_times.entry(syscall_id as u16).or_insert(0);
         *count += 1;
     }
     pub fn add_stride(&mut self){
         self.stride += BIG_STRIDE / self.priority;
     }
+    /// Whether one more process stays within the process limit. There are
+    /// no users, so like those of one Linux user, all processes count: the
+    /// children of children too, and those of other sessions.
+    pub fn may_add_child(&self) -> bool {
+        process_count() < self.rlimits.cur(RLIMIT_NPROC)
+    }
+    /// Whether mapping `len` more bytes stays within the address space and
+    /// resident memory limits.
+    pub fn may_map(&self, len: usize) -> bool {
+        let pages = len.saturating_add(PAGE_SIZE - 1) / PAGE_SIZE;
+        let size = |pages: usize| pages.saturating_mul(PAGE_SIZE);
+        size(self.memory_set.mapped_pages() + pages) <= self.rlimits.cur(RLIMIT_AS)
+            && size(self.memory_set.resident_pages() + pages) <= self.rlimits.cur(RLIMIT_RSS)
+    }
+    /// Charge a timer tick, return false once the CPU time limit is used up.
+    pub fn charge_tick(&mut self) -> bool {
+        self.cpu_ticks += 1;
+        self.cpu_ticks <= self.rlimits.cur(RLIMIT_CPU).saturating_mul(TICKS_PER_SEC)
+    }
+    /// CPU time used so far, including the current run if there is one.
+    pub fn total_cpu_time_ns(&self, now_ns: usize) -> usize {
+        let usage = if self.task_status == TaskStatus::Running {
+            self.accounting.current(now_ns)
+        } else {
+            self.accounting.usage
+        };
+        usage.user_ns + usage.system_ns
+    }
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -225,6 +379,8 @@ pub enum TaskStatus {
     Ready,
     Running,
     Zombie,
+    /// traced and stopped until the tracer resumes it
+    Stopped,
 }
 
 
@@ -0,0 +1,75 @@
+//! Per-process syscall tracing
+//!
+//! A traced process logs each syscall it makes into a ring buffer of its
+//! own, which a tracer drains with `sys_trace_read`. When the buffer is full
+//! the oldest entries are overwritten.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// entries kept per traced process
+const TRACE_RING_SIZE: usize = 128;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct TraceEntry {
+    pub id: usize,
+    pub args: [usize; 6],
+    /// meaningful only once `finished` is 1
+    pub ret: isize,
+    /// when the syscall was made, in microseconds since boot
+    pub start_us: usize,
+    pub duration_us: usize,
+    /// 0 while the syscall has not returned, e.g. `exit`
+    pub finished: usize,
+}
+
+pub struct SyscallTrace {
+    entries: VecDeque<TraceEntry>,
+}
+
+impl SyscallTrace {
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(TRACE_RING_SIZE),
+        }
+    }
+    /// Log a syscall that is about to be handled.
+    pub fn begin(&mut self, id: usize, args: [usize; 6], now_us: usize) {
+        if self.entries.len() == TRACE_RING_SIZE {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(TraceEntry {
+            id,
+            args,
+            ret: 0,
+            start_us: now_us,
+            duration_us: 0,
+            finished: 0,
+        });
+    }
+    /// Complete the entry logged by the last [`SyscallTrace::begin`].
+    pub fn end(&mut self, ret: isize, now_us: usize) {
+        if let Some(entry) = self.entries.back_mut() {
+            if entry.finished == 0 {
+                entry.ret = ret;
+                entry.duration_us = now_us - entry.start_us;
+                entry.finished = 1;
+            }
+        }
+    }
+    /// Take at most `max` of the oldest entries. A syscall still in
+    /// progress is left alone unless `unfinished` is set.
+    pub fn drain(&mut self, max: usize, unfinished: bool) -> Vec<TraceEntry> {
+        let mut taken = Vec::new();
+        while taken.len() < max {
+            match self.entries.front() {
+                Some(entry) if entry.finished == 1 || unfinished => {
+                    taken.push(self.entries.pop_front().unwrap());
+                }
+                _ => break,
+            }
+        }
+        taken
+    }
+}
@@ -1,16 +1,60 @@
 use crate::config::CLOCK_FREQ;
+use crate::drivers::read_rtc_ns;
 use crate::sbi::set_timer;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use riscv::register::time;
 
-const TICKS_PER_SEC: usize = 100;
+pub const TICKS_PER_SEC: usize = 100;
 const MICRO_PER_SEC: usize = 1_000_000;
+const NANO_PER_SEC: usize = 1_000_000_000;
+/// `TM` bit of `scounteren`, lets U-mode read `time`
+const SCOUNTEREN_TM: usize = 1 << 1;
+
+/// `time` when the kernel started
+static BOOT_TICKS: AtomicUsize = AtomicUsize::new(0);
+/// wall-clock time at boot in nanoseconds since the epoch
+static REALTIME_BASE_NS: AtomicUsize = AtomicUsize::new(0);
+
+/// Start counting time since boot from now and let user space read the
+/// clock with `rdtime`.
+pub fn init() {
+    BOOT_TICKS.store(time::read(), Ordering::Relaxed);
+    unsafe {
+        core::arch::asm!("csrs scounteren, {}", in(reg) SCOUNTEREN_TM);
+    }
+}
+
+/// Seed the wall clock from the RTC, once its registers are mapped.
+pub fn init_realtime() {
+    let base = read_rtc_ns() as usize - get_time_ns();
+    REALTIME_BASE_NS.store(base, Ordering::Relaxed);
+}
+
+pub fn boot_ticks() -> usize {
+    BOOT_TICKS.load(Ordering::Relaxed)
+}
+
+pub fn realtime_base_ns() -> usize {
+    REALTIME_BASE_NS.load(Ordering::Relaxed)
+}
 
 pub fn get_time() -> usize {
     time::read()
 }
 
+/// microseconds since boot
 pub fn get_time_us() -> usize {
-    time::read() / (CLOCK_FREQ / MICRO_PER_SEC)
+    (time::read() - boot_ticks()) / (CLOCK_FREQ / MICRO_PER_SEC)
+}
+
+/// nanoseconds since boot
+pub fn get_time_ns() -> usize {
+    (time::read() - boot_ticks()) * (NANO_PER_SEC / CLOCK_FREQ)
+}
+
+/// nanoseconds since the epoch
+pub fn get_realtime_ns() -> usize {
+    realtime_base_ns() + get_time_ns()
 }
 
 pub fn set_next_trigger() {
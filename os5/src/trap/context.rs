@@ -1,5 +1,6 @@
 //! Implementation of [`TrapContext`]
 
+use crate::config::VDSO;
 use riscv::register::sstatus::{self, Sstatus, SPP};
 
 #[repr(C)]
@@ -13,6 +14,15 @@ pub struct TrapContext {
     pub trap_handler: usize,
 }
 
+/// registers of the kernel code a trap interrupted, saved by
+/// `__kernel_trap` on the kernel stack
+#[repr(C)]
+pub struct KernelTrapContext {
+    pub x: [usize; 32],
+    pub sstatus: usize,
+    pub sepc: usize,
+}
+
 impl TrapContext {
     pub fn set_sp(&mut self, sp: usize) {
         self.x[2] = sp;
@@ -35,6 +45,8 @@ impl TrapContext {
             trap_handler,
         };
         cx.set_sp(sp);
+        // where the user lib finds the time page
+        cx.x[12] = VDSO;
         cx
     }
 }
@@ -0,0 +1,47 @@
+//! Exception fixups for kernel code which may fault
+//!
+//! Each entry of `__ex_table` pairs the address of an instruction allowed
+//! to fault with where to go on instead. [`super::trap_from_kernel`] looks
+//! a faulting `sepc` up here before giving up on the kernel.
+
+extern "C" {
+    /// return the number of bytes left uncopied
+    fn __copy_nofault(dst: *mut u8, src: *const u8, len: usize) -> usize;
+}
+
+/// an entry of `__ex_table`, as laid out in `trap.S`
+#[repr(C)]
+struct Fixup {
+    insn: usize,
+    fixup: usize,
+}
+
+fn table() -> &'static [Fixup] {
+    extern "C" {
+        fn sex_table();
+        fn eex_table();
+    }
+    let start = sex_table as usize;
+    let len = (eex_table as usize - start) / core::mem::size_of::<Fixup>();
+    unsafe { core::slice::from_raw_parts(start as *const Fixup, len) }
+}
+
+/// Where to resume after the instruction at `pc` faulted, if it may.
+pub fn search(pc: usize) -> Option<usize> {
+    table()
+        .iter()
+        .find(|entry| entry.insn == pc)
+        .map(|entry| entry.fixup)
+}
+
+/// Fill `dst` from kernel address `src`, return false if a page fault
+/// stopped the copy, e.g. on a page that is not mapped.
+pub fn copy_from_nofault(dst: &mut [u8], src: usize) -> bool {
+    unsafe { __copy_nofault(dst.as_mut_ptr(), src as *const u8, dst.len()) == 0 }
+}
+
+/// Copy `src` to kernel address `dst`, return false if a page fault
+/// stopped the copy.
+pub fn copy_to_nofault(dst: usize, src: &[u8]) -> bool {
+    unsafe { __copy_nofault(dst as *mut u8, src.as_ptr(), src.len()) == 0 }
+}
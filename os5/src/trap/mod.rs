@@ -12,29 +12,52 @@
 //! It then calls different functionality based on what exactly the exception
 //! was. For example, timer interrupts trigger task preemption, and syscalls go
 //! to [`syscall()`].
+//!
+//! Traps taken in the kernel go through `__kernel_trap` instead, which saves
+//! the interrupted registers on the kernel stack for [`trap_from_kernel()`].
+//! Syscalls run with interrupts on: a timer interrupt then only marks a tick
+//! pending, which is charged at the next [`preempt_point()`], as nothing is
+//! safe to borrow in the middle of arbitrary kernel code.
 mod context;
+mod fixup;
 
+use crate::backtrace::print_trap_backtrace;
 use crate::config::{TRAMPOLINE, TRAP_CONTEXT};
+use crate::sbi::shutdown;
 use crate::syscall::syscall;
 use crate::task::{
-    current_trap_cx, current_user_token, exit_current_and_run_next, suspend_current_and_run_next, increase_current_task_syscall
+    current_task, current_trap_cx, current_user_token, exit_current_and_run_next, preempt_current_and_run_next, increase_current_task_syscall,
+    charge_current_tick, trace_syscall_begin, trace_syscall_end, account_trap_entry,
+    account_trap_return, count_current_page_fault, current_task_label, dump_current_core,
+    Fault, Termination, CPU_LIMIT_KILLED, SIGILL, SIGSEGV, SIGTRAP,
+    current_is_traced, handle_pending_stop, stop_current_and_run_next, print_current_backtrace,
+    current_backtrace,
+    try_current_task_label,
 };
 use crate::timer::set_next_trigger;
+use core::sync::atomic::{AtomicBool, Ordering};
 use riscv::register::{
     mtvec::TrapMode,
-    scause::{self, Exception, Interrupt, Trap},
-    sie, stval, stvec,
+    scause::{self, Exception, Interrupt, Scause, Trap},
+    sie, sstatus, stval, stvec,
 };
 
 core::arch::global_asm!(include_str!("trap.S"));
 
+/// a timer interrupt came while in the kernel, and its tick is yet to be
+/// charged
+static TICK_PENDING: AtomicBool = AtomicBool::new(false);
+
 pub fn init() {
     set_kernel_trap_entry();
 }
 
 fn set_kernel_trap_entry() {
+    extern "C" {
+        fn __kernel_trap();
+    }
     unsafe {
-        stvec::write(trap_from_kernel as usize, TrapMode::Direct);
+        stvec::write(__kernel_trap as usize, TrapMode::Direct);
     }
 }
 
@@ -50,17 +73,56 @@ pub fn enable_timer_interrupt() {
     }
 }
 
+/// Let interrupts in while in the kernel, once `stvec` is `__kernel_trap`.
+fn enable_kernel_interrupts() {
+    unsafe {
+        sstatus::set_sie();
+    }
+}
+
+/// Keep interrupts out until `sret` turns them back on in user space.
+fn disable_kernel_interrupts() {
+    unsafe {
+        sstatus::clear_sie();
+    }
+}
+
+/// Charge a timer tick to the current task and run others.
+fn handle_tick() {
+    if !charge_current_tick() {
+        println!(
+            "[kernel] CPU time limit exceeded in application {}, killed.",
+            current_task_label(),
+        );
+        exit_current_and_run_next(CPU_LIMIT_KILLED);
+    }
+    preempt_current_and_run_next();
+}
+
+/// Take a tick that came while in the kernel. Only call this from code that
+/// holds no borrows, as other tasks run meanwhile.
+pub fn preempt_point() {
+    if TICK_PENDING.swap(false, Ordering::Relaxed) {
+        handle_tick();
+    }
+}
+
 #[no_mangle]
 pub fn trap_handler() -> ! {
     set_kernel_trap_entry();
+    account_trap_entry();
     let scause = scause::read();
     let stval = stval::read();
     match scause.cause() {
         Trap::Exception(Exception::UserEnvCall) => {
+            enable_kernel_interrupts();
             let mut cx = current_trap_cx();
             cx.sepc += 4;
             increase_current_task_syscall(cx.x[17]);
-            let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]) as usize;
+            let args = [cx.x[10], cx.x[11], cx.x[12], cx.x[13], cx.x[14], cx.x[15]];
+            trace_syscall_begin(cx.x[17], args);
+            let result = syscall(cx.x[17], args);
+            trace_syscall_end(result);
             cx = current_trap_cx();
             cx.x[10] = result as usize;
         }
@@ -70,23 +132,79 @@ pub fn trap_handler() -> ! {
         Trap::Exception(Exception::InstructionPageFault) |
         Trap::Exception(Exception::LoadFault) |
         Trap::Exception(Exception::LoadPageFault) => {
+            count_current_page_fault();
+            let fault = Fault {
+                cause: scause.bits(),
+                addr: stval,
+                pc: current_trap_cx().sepc,
+            };
+            let backtrace = current_backtrace();
+            let core_dumped = dump_current_core(SIGSEGV, fault, &backtrace);
             println!(
-                "[kernel] {:?} in application, bad addr = {:#x}, bad instruction = {:#x}, core dumped.",
+                "[kernel] {:?} in application {}, bad addr = {:#x}, bad instruction = {:#x}{}.",
                 scause.cause(),
+                current_task_label(),
                 stval,
-                current_trap_cx().sepc,
+                fault.pc,
+                core_dumped_suffix(core_dumped),
             );
-            // page fault exit code
-            exit_current_and_run_next(-2);
+            print_current_backtrace(&backtrace);
+            exit_current_and_run_next(Termination::Faulted {
+                signal: SIGSEGV,
+                fault,
+                core_dumped,
+            });
         }
         Trap::Exception(Exception::IllegalInstruction) => {
-            println!("[kernel] IllegalInstruction in application, core dumped.");
-            // illegal instruction exit code
-            exit_current_and_run_next(-3);
+            let fault = Fault {
+                cause: scause.bits(),
+                addr: stval,
+                pc: current_trap_cx().sepc,
+            };
+            let backtrace = current_backtrace();
+            let core_dumped = dump_current_core(SIGILL, fault, &backtrace);
+            println!(
+                "[kernel] IllegalInstruction in application {}{}.",
+                current_task_label(),
+                core_dumped_suffix(core_dumped),
+            );
+            print_current_backtrace(&backtrace);
+            exit_current_and_run_next(Termination::Faulted {
+                signal: SIGILL,
+                fault,
+                core_dumped,
+            });
+        }
+        Trap::Exception(Exception::Breakpoint) => {
+            // a breakpoint or the end of a single step; the tracer finds the
+            // pc on the `ebreak` and decides how to go on
+            if current_is_traced() {
+                stop_current_and_run_next(SIGTRAP);
+            } else {
+                let fault = Fault {
+                    cause: scause.bits(),
+                    addr: stval,
+                    pc: current_trap_cx().sepc,
+                };
+                let backtrace = current_backtrace();
+                let core_dumped = dump_current_core(SIGTRAP, fault, &backtrace);
+                println!(
+                    "[kernel] Breakpoint in application {} at {:#x}, not traced{}.",
+                    current_task_label(),
+                    fault.pc,
+                    core_dumped_suffix(core_dumped),
+                );
+                print_current_backtrace(&backtrace);
+                exit_current_and_run_next(Termination::Faulted {
+                    signal: SIGTRAP,
+                    fault,
+                    core_dumped,
+                });
+            }
         }
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
             set_next_trigger();
-            suspend_current_and_run_next();
+            handle_tick();
         }
         _ => {
             panic!(
@@ -99,8 +217,24 @@ pub fn trap_handler() -> ! {
     trap_return();
 }
 
+fn core_dumped_suffix(core_dumped: bool) -> &'static str {
+    if core_dumped {
+        ", core dumped"
+    } else {
+        ""
+    }
+}
+
 #[no_mangle]
 pub fn trap_return() -> ! {
+    preempt_point();
+    handle_pending_stop();
+    let killed = current_task().unwrap().inner_exclusive_access().killed;
+    if let Some(termination) = killed {
+        exit_current_and_run_next(termination);
+    }
+    account_trap_return();
+    disable_kernel_interrupts();
     set_user_trap_entry();
     let trap_cx_ptr = TRAP_CONTEXT;
     let user_satp = current_user_token();
@@ -121,9 +255,44 @@ pub fn trap_return() -> ! {
     }
 }
 
+/// Handle a trap taken in the kernel, whose registers `__kernel_trap` saved
+/// in `cx` and restores on return.
 #[no_mangle]
-pub fn trap_from_kernel() -> ! {
-    panic!("a trap from kernel!");
+pub extern "C" fn trap_from_kernel(cx: &mut KernelTrapContext) {
+    let scause = scause::read();
+    let stval = stval::read();
+    match scause.cause() {
+        Trap::Interrupt(Interrupt::SupervisorTimer) => {
+            set_next_trigger();
+            TICK_PENDING.store(true, Ordering::Relaxed);
+        }
+        Trap::Exception(Exception::LoadFault)
+        | Trap::Exception(Exception::LoadPageFault)
+        | Trap::Exception(Exception::StoreFault)
+        | Trap::Exception(Exception::StorePageFault) => match fixup::search(cx.sepc) {
+            Some(fixup) => cx.sepc = fixup,
+            None => kernel_fault(cx, scause, stval),
+        },
+        _ => kernel_fault(cx, scause, stval),
+    }
+}
+
+/// Report a trap the kernel can't recover from, with the frames it
+/// interrupted, and stop.
+fn kernel_fault(cx: &KernelTrapContext, scause: Scause, stval: usize) -> ! {
+    println!(
+        "[kernel] Panicked: {:?} in kernel, bad addr = {:#x}, bad instruction = {:#x}",
+        scause.cause(),
+        stval,
+        cx.sepc,
+    );
+    match try_current_task_label() {
+        Some(label) => println!("[kernel] Current task: {}", label),
+        None => println!("[kernel] Current task: none or busy"),
+    }
+    print_trap_backtrace(cx.sepc, cx.x[8]);
+    shutdown()
 }
 
-pub use context::TrapContext;
+pub use context::{KernelTrapContext, TrapContext};
+pub use fixup::{copy_from_nofault, copy_to_nofault};
@@ -0,0 +1,41 @@
+//! Time page shared read-only with every process
+//!
+//! A single frame holding [`VdsoData`] is mapped at [`VDSO`] into each user
+//! memory set, so that processes can read `time` with `rdtime` and convert
+//! it themselves instead of trapping into `sys_get_time`. A new process
+//! finds the address of the page in `a2`.
+//!
+//! [`VDSO`]: crate::config::VDSO
+
+use crate::config::CLOCK_FREQ;
+use crate::mm::{frame_alloc, FrameTracker, PhysPageNum};
+use crate::timer::{boot_ticks, realtime_base_ns};
+use lazy_static::*;
+
+#[repr(C)]
+pub struct VdsoData {
+    /// ticks of `time` per second
+    pub clock_freq: usize,
+    /// `time` when the kernel booted
+    pub boot_ticks: usize,
+    /// wall-clock time at boot in nanoseconds since the epoch, 0 while it
+    /// is not known
+    pub realtime_base_ns: usize,
+}
+
+lazy_static! {
+    static ref VDSO_FRAME: FrameTracker = frame_alloc().unwrap();
+}
+
+/// Publish the clock, after the frame allocator and the timer are set up.
+pub fn init() {
+    *VDSO_FRAME.ppn.get_mut::<VdsoData>() = VdsoData {
+        clock_freq: CLOCK_FREQ,
+        boot_ticks: boot_ticks(),
+        realtime_base_ns: realtime_base_ns(),
+    };
+}
+
+pub fn vdso_ppn() -> PhysPageNum {
+    VDSO_FRAME.ppn
+}
@@ -4,8 +4,8 @@
 extern crate user_lib;
 
 use user_lib::{
-    get_time, println, sleep, task_info, TaskInfo, TaskStatus, SYSCALL_EXIT, SYSCALL_GETTIMEOFDAY,
-    SYSCALL_TASK_INFO, SYSCALL_WRITE, SYSCALL_YIELD,
+    get_time_trap as get_time, println, sleep, task_info, TaskInfo, TaskStatus, SYSCALL_EXIT,
+    SYSCALL_GETTIMEOFDAY, SYSCALL_TASK_INFO, SYSCALL_WRITE, SYSCALL_YIELD,
 };
 
 #[no_mangle]
@@ -0,0 +1,139 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+use user_lib::wait::{wcoredump, wtermsig, SIGSEGV};
+use user_lib::{exit, fork, sys, ExitInfo};
+
+/// 测试用户态回溯：子进程在多层调用中缺页时，内核沿帧指针链回溯并解析符号后将其杀死；
+/// 父进程从子进程 core 文件的 RCORE 注释中取回回溯并检查其中的函数名；
+/// 帧指针被破坏或调用链过深时也能正常结束。
+/// 理想输出：Test backtrace OK!
+
+const PT_NOTE: u32 = 4;
+/// the `RCORE` note of the backtrace printed by the kernel
+const NT_RCORE_BACKTRACE: u32 = 2;
+/// more than the notes of a dump take
+const NOTES_MAX: usize = 4096;
+static mut NOTES: [u8; NOTES_MAX] = [0; NOTES_MAX];
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    let mut raw = [0u8; 4];
+    raw.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(raw)
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> usize {
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(raw) as usize
+}
+
+#[inline(never)]
+fn fault() -> ! {
+    unsafe {
+        (0usize as *mut u8).write_volatile(0);
+    }
+    unreachable!();
+}
+
+#[inline(never)]
+fn nested(depth: usize) {
+    if depth == 0 {
+        fault();
+    }
+    nested(depth - 1);
+    // keep the call from becoming a jump
+    unsafe { asm!("nop") };
+}
+
+#[inline(never)]
+fn corrupt_fp() -> ! {
+    unsafe {
+        asm!("li s0, 0x12345", "sd zero, 0(zero)", options(noreturn));
+    }
+}
+
+/// Read the backtrace note from the core of the reaped child `pid`.
+fn backtrace_of(pid: usize) -> &'static str {
+    // the first program header is the notes
+    let mut header = [0u8; 64 + 56];
+    sys::coredump(pid, 0, &mut header).unwrap();
+    let phoff = u64_at(&header, 32);
+    assert_eq!(phoff, 64);
+    assert_eq!(u32_at(&header, phoff), PT_NOTE);
+    let start = u64_at(&header, phoff + 8);
+    let size = u64_at(&header, phoff + 32);
+    assert!(size <= NOTES_MAX);
+    let notes = unsafe { &mut NOTES[..size] };
+    sys::coredump(pid, start, notes).unwrap();
+    let mut offset = 0;
+    while offset < size {
+        let namesz = u32_at(notes, offset) as usize;
+        let descsz = u32_at(notes, offset + 4) as usize;
+        let kind = u32_at(notes, offset + 8);
+        let desc = offset + 12 + (namesz + 3) / 4 * 4;
+        if kind == NT_RCORE_BACKTRACE && &notes[offset + 12..offset + 18] == b"RCORE\0" {
+            return core::str::from_utf8(&notes[desc..desc + descsz]).unwrap();
+        }
+        offset = desc + (descsz + 3) / 4 * 4;
+    }
+    panic!("no backtrace in the core");
+}
+
+/// Run `f` in a child, which must die of a fault, and return its backtrace.
+fn run(f: fn()) -> &'static str {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut info = ExitInfo::default();
+    assert_eq!(sys::waitpid_info(pid, &mut info), Ok(pid as usize));
+    assert_eq!(wtermsig(info.status), SIGSEGV);
+    assert!(wcoredump(info.status));
+    backtrace_of(pid as usize)
+}
+
+/// The function of each frame, innermost first.
+fn functions(backtrace: &str) -> impl Iterator<Item = &str> {
+    backtrace
+        .lines()
+        .filter_map(|line| line.split(" in ").nth(1))
+        .map(|at| at.split('+').next().unwrap())
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let backtrace = run(|| nested(3));
+    let mut frames = functions(backtrace);
+    assert_eq!(frames.next(), Some("ch5_backtrace::fault"));
+    for _ in 0..4 {
+        assert_eq!(frames.next(), Some("ch5_backtrace::nested"));
+    }
+    assert!(!backtrace.ends_with("...\n"));
+
+    // longer than the kernel prints
+    let backtrace = run(|| nested(64));
+    assert_eq!(functions(backtrace).next(), Some("ch5_backtrace::fault"));
+    assert_eq!(
+        functions(backtrace)
+            .filter(|&name| name == "ch5_backtrace::nested")
+            .count(),
+        15
+    );
+    assert!(backtrace.ends_with("...\n"));
+
+    // a frame pointer into nowhere ends the walk
+    let backtrace = run(|| corrupt_fp());
+    assert_eq!(
+        functions(backtrace).next(),
+        Some("ch5_backtrace::corrupt_fp")
+    );
+    assert!(!backtrace.ends_with("...\n"));
+    println!("Test backtrace OK!");
+    0
+}
@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, sys, sys_clock_gettime, waitpid, Errno, TimeSpec, CLOCK_MONOTONIC,
+    CLOCK_PROCESS_CPUTIME_ID, CLOCK_REALTIME,
+};
+
+/// 测试 clock_gettime：墙上时间来自 RTC，单调时钟与时间页一致，
+/// 进程 CPU 时间只在进程运行时增长。
+/// 理想输出：Test clock OK!
+
+/// 2020-01-01 00:00:00 UTC
+const YEAR_2020: usize = 1577836800;
+const NANO_PER_MS: usize = 1_000_000;
+
+fn trapping(clock_id: usize) -> TimeSpec {
+    let mut time = TimeSpec::default();
+    assert_eq!(0, sys_clock_gettime(clock_id, &mut time));
+    time
+}
+
+fn now(clock_id: usize) -> TimeSpec {
+    sys::clock_gettime(clock_id).unwrap()
+}
+
+fn burn_cpu() -> i32 {
+    let cpu_start = now(CLOCK_PROCESS_CPUTIME_ID).as_ns();
+    let mono_start = now(CLOCK_MONOTONIC).as_ns();
+    assert!(cpu_start < 10 * NANO_PER_MS, "a new process has used no CPU yet");
+    let mut cpu = cpu_start;
+    while cpu - cpu_start < 50 * NANO_PER_MS {
+        let next = now(CLOCK_PROCESS_CPUTIME_ID).as_ns();
+        assert!(next >= cpu);
+        cpu = next;
+    }
+    assert!(now(CLOCK_MONOTONIC).as_ns() - mono_start >= cpu - cpu_start);
+    0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(sys::clock_gettime(7), Err(Errno::EINVAL));
+
+    let realtime = now(CLOCK_REALTIME);
+    assert!(realtime.sec > YEAR_2020, "the RTC was not read");
+    assert!(realtime.nsec < 1_000_000_000);
+    for clock_id in [CLOCK_REALTIME, CLOCK_MONOTONIC] {
+        let before = trapping(clock_id);
+        let page = now(clock_id);
+        let after = trapping(clock_id);
+        assert!(before <= page && page <= after);
+    }
+
+    let pid = fork();
+    if pid == 0 {
+        exit(burn_cpu());
+    }
+    let mut exit_code = -1;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    assert_eq!(exit_code, 0);
+    println!("Test clock OK!");
+    0
+}
@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{comm_str, exit, fork, getpid, spawn, sys, sys_prctl, waitpid, yield_, Errno};
+
+/// 测试进程名：取自程序名，fork 时继承，spawn 时取新程序名，
+/// 可通过 prctl 修改，过长时截断。
+/// 理想输出：Test process names OK!
+
+const ZOMBIE: usize = 3;
+
+fn name_of(pid: usize) -> [u8; 16] {
+    sys::proc_info(pid).unwrap().name
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid() as usize;
+    assert_eq!(comm_str(&sys::get_name().unwrap()), "ch5_comm");
+    assert_eq!(comm_str(&name_of(pid)), "ch5_comm");
+
+    let child = fork();
+    if child == 0 {
+        assert_eq!(comm_str(&sys::get_name().unwrap()), "ch5_comm");
+        sys::set_name("worker\0").unwrap();
+        exit(0);
+    }
+    let child = child as usize;
+    while sys::proc_info(child).unwrap().status != ZOMBIE {
+        yield_();
+    }
+    // the name outlives the process until it is reaped
+    assert_eq!(comm_str(&name_of(child)), "worker");
+    let mut exit_code = 0;
+    assert_eq!(waitpid(child, &mut exit_code), child as isize);
+    assert_eq!(comm_str(&sys::get_name().unwrap()), "ch5_comm");
+
+    let spawned = spawn("ch5_exit0\0");
+    assert!(spawned > 0);
+    assert_eq!(comm_str(&name_of(spawned as usize)), "ch5_exit0");
+    assert_eq!(waitpid(spawned as usize, &mut exit_code), spawned);
+
+    sys::set_name("a-rather-long-process-name\0").unwrap();
+    assert_eq!(comm_str(&sys::get_name().unwrap()), "a-rather-long-p");
+    sys::set_name("ch5_comm\0").unwrap();
+
+    assert_eq!(Errno::check(sys_prctl(99, 0)).err(), Some(Errno::EINVAL));
+    println!("Test process names OK!");
+    0
+}
@@ -0,0 +1,183 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::wait::{wcoredump, wtermsig, SIGSEGV};
+use user_lib::{exit, fork, sys, yield_, Errno, ExitInfo, RLimit, RLIMIT_CORE, RLIM_INFINITY};
+
+/// 测试 core dump：子进程缺页后父进程可取回 ELF core 文件，其中包含
+/// 寄存器、pid 与可写内存的内容；RLIMIT_CORE 为 0 时不生成；
+/// 未回收的 core 总量超出内核预算时不再生成，而内核不会耗尽堆。
+/// 理想输出：Test core dump OK!
+
+const PATTERN_LEN: usize = 64;
+static mut PATTERN: [u8; PATTERN_LEN] = [0; PATTERN_LEN];
+
+/// where the dump is read to, mapped after the child is gone so that it
+/// is not part of the dump itself
+const BUF: usize = 0x1000_0000;
+const PAGE_SIZE: usize = 4096;
+
+const ET_CORE: u16 = 4;
+const EM_RISCV: u16 = 243;
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const NT_PRSTATUS: u32 = 1;
+
+const ZOMBIE: usize = 3;
+/// mapped by each child of the budget test: two such dumps fit in the
+/// kernel budget of 1 MiB for all dumps, three do not
+const BIG_LEN: usize = 400 * 1024;
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    let mut raw = [0u8; 4];
+    raw.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(raw)
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> usize {
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(raw) as usize
+}
+
+fn fault() -> ! {
+    unsafe {
+        (0usize as *mut u8).write_volatile(0);
+    }
+    unreachable!();
+}
+
+fn spawn_faulting(core_limit: usize) -> (usize, ExitInfo) {
+    let pid = fork();
+    if pid == 0 {
+        let limit = RLimit {
+            cur: core_limit,
+            max: RLIM_INFINITY,
+        };
+        sys::setrlimit(RLIMIT_CORE, &limit).unwrap();
+        unsafe {
+            for (i, byte) in PATTERN.iter_mut().enumerate() {
+                *byte = i as u8 ^ 0x5a;
+            }
+        }
+        fault();
+    }
+    let mut info = ExitInfo::default();
+    assert_eq!(sys::waitpid_info(pid, &mut info), Ok(pid as usize));
+    (pid as usize, info)
+}
+
+/// Check the registers and pid in `NT_PRSTATUS`.
+fn check_notes(core: &[u8], pid: usize, info: &ExitInfo) {
+    let phoff = u64_at(core, 32);
+    let note = phoff;
+    assert_eq!(u32_at(core, note), PT_NOTE);
+    let start = u64_at(core, note + 8);
+    let end = start + u64_at(core, note + 32);
+    let mut offset = start;
+    let mut found = false;
+    while offset < end {
+        let namesz = u32_at(core, offset) as usize;
+        let descsz = u32_at(core, offset + 4) as usize;
+        let kind = u32_at(core, offset + 8);
+        let desc = offset + 12 + (namesz + 3) / 4 * 4;
+        if kind == NT_PRSTATUS && &core[offset + 12..offset + 16] == b"CORE" {
+            // pr_cursig, pr_pid, then pc as the first of pr_reg
+            assert_eq!(u16_at(core, desc + 12) as i32, SIGSEGV);
+            assert_eq!(u32_at(core, desc + 32) as usize, pid);
+            assert_eq!(u64_at(core, desc + 112), info.pc);
+            found = true;
+        }
+        offset = desc + (descsz + 3) / 4 * 4;
+    }
+    assert!(found);
+}
+
+/// Find `PATTERN` in the writable memory of the dump.
+fn check_memory(core: &[u8]) {
+    let addr = unsafe { PATTERN.as_ptr() as usize };
+    let phoff = u64_at(core, 32);
+    let phentsize = u16_at(core, 54) as usize;
+    let phnum = u16_at(core, 56) as usize;
+    let segment = (0..phnum)
+        .map(|i| phoff + i * phentsize)
+        .filter(|&ph| u32_at(core, ph) == PT_LOAD)
+        .find(|&ph| {
+            let vaddr = u64_at(core, ph + 16);
+            vaddr <= addr && addr + PATTERN_LEN <= vaddr + u64_at(core, ph + 40)
+        })
+        .expect("no segment holds the pattern");
+    assert!(u64_at(core, segment + 32) > 0, "writable data not dumped");
+    let offset = u64_at(core, segment + 8) + addr - u64_at(core, segment + 16);
+    for (i, &byte) in core[offset..offset + PATTERN_LEN].iter().enumerate() {
+        assert_eq!(byte, i as u8 ^ 0x5a);
+    }
+}
+
+/// Fault in four children with large dumps, which all wait to be reaped.
+fn test_budget() {
+    let mut pids = [0; 4];
+    for pid in pids.iter_mut() {
+        *pid = fork() as usize;
+        if *pid == 0 {
+            sys::mmap(BUF, BIG_LEN, 0b011).unwrap();
+            fault();
+        }
+    }
+    for &pid in pids.iter() {
+        while sys::proc_info(pid).unwrap().status != ZOMBIE {
+            yield_();
+        }
+    }
+    let mut dumped = 0;
+    for &pid in pids.iter() {
+        let mut info = ExitInfo::default();
+        assert_eq!(sys::waitpid_info(pid as isize, &mut info), Ok(pid));
+        assert_eq!(wtermsig(info.status), SIGSEGV);
+        if wcoredump(info.status) {
+            dumped += 1;
+        }
+    }
+    assert_eq!(dumped, 2);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let (pid, info) = spawn_faulting(RLIM_INFINITY);
+    assert_eq!(wtermsig(info.status), SIGSEGV);
+    assert!(wcoredump(info.status));
+    let size = sys::coredump(pid, 0, &mut []).unwrap();
+    assert!(size > PAGE_SIZE);
+
+    let len = (size + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+    sys::mmap(BUF, len, 0b011).unwrap();
+    let core = unsafe { core::slice::from_raw_parts_mut(BUF as *mut u8, size) };
+    assert_eq!(sys::coredump(pid, 0, core), Ok(size));
+    assert_eq!(&core[..4], b"\x7fELF");
+    assert_eq!(u16_at(core, 16), ET_CORE);
+    assert_eq!(u16_at(core, 18), EM_RISCV);
+    check_notes(core, pid, &info);
+    check_memory(core);
+    // reads past the end copy nothing
+    let mut tail = [0u8; 8];
+    assert_eq!(sys::coredump(pid, size, &mut tail), Ok(size));
+    sys::munmap(BUF, len).unwrap();
+
+    // no dump at all if it can't be complete
+    let (small, info) = spawn_faulting(0);
+    assert_eq!(wtermsig(info.status), SIGSEGV);
+    assert!(!wcoredump(info.status));
+    assert_eq!(sys::coredump(small, 0, &mut []).err(), Some(Errno::ENOENT));
+    // the older dump is still there
+    assert_eq!(sys::coredump(pid, 0, &mut []), Ok(size));
+    test_budget();
+    println!("Test core dump OK!");
+    0
+}
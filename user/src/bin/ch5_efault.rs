@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::{slice, str};
+use user_lib::{mmap, munmap, sys, Errno, TaskInfo, TaskStatus};
+
+/// 向系统调用传入非法指针、超长的长度或字符串，内核应返回 EFAULT 而不是 panic；
+/// 跨页的结构体应被完整写入。
+/// 理想输出：Test EFAULT OK!
+
+const PAGE_SIZE: usize = 4096;
+/// below the program image, never mapped
+const UNMAPPED: usize = 0x1000;
+/// kernel only, lacks the U flag
+const TRAP_CONTEXT: usize = usize::MAX - 2 * PAGE_SIZE + 1;
+/// the first address past the lower half of Sv39, which is not canonical
+const NON_CANONICAL: usize = 1 << 38;
+/// the longest path the kernel reads, nul included
+const MAX_STR_LEN: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let bad = |addr: usize| unsafe { &mut *(addr as *mut TaskInfo) };
+    assert_eq!(Err(Errno::EFAULT), sys::task_info(bad(UNMAPPED)));
+    assert_eq!(Err(Errno::EFAULT), sys::task_info(bad(TRAP_CONTEXT)));
+    assert_eq!(Err(Errno::EFAULT), sys::task_info(bad(NON_CANONICAL - 16)));
+    // the code is mapped without W
+    assert_eq!(Err(Errno::EFAULT), sys::task_info(bad(main as usize)));
+    let buf = unsafe { slice::from_raw_parts(UNMAPPED as *const u8, 16) };
+    assert_eq!(Err(Errno::EFAULT), sys::write(1, buf));
+    // far more than the kernel heap, starting on a mapped page
+    let buf = unsafe { slice::from_raw_parts(main as usize as *const u8, 1 << 30) };
+    assert_eq!(Err(Errno::EFAULT), sys::write(1, buf));
+
+    // straddle two mapped pages
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, PAGE_SIZE * 2, 3));
+    let info = bad(start + PAGE_SIZE - 16);
+    assert_eq!(Ok(()), sys::task_info(info));
+    assert_eq!(info.status, TaskStatus::Running);
+    assert!(info.syscall_times[64] > 0);
+    // only the first page is mapped
+    assert_eq!(0, munmap(start, PAGE_SIZE * 2));
+    assert_eq!(0, mmap(start, PAGE_SIZE, 3));
+    assert_eq!(Err(Errno::EFAULT), sys::task_info(bad(start + PAGE_SIZE - 16)));
+    assert_eq!(0, munmap(start, PAGE_SIZE));
+
+    // a path too long to read, though it ends in mapped memory
+    assert_eq!(0, mmap(start, PAGE_SIZE * 2, 3));
+    let bytes = unsafe { slice::from_raw_parts_mut(start as *mut u8, MAX_STR_LEN + 1) };
+    bytes.fill(b'a');
+    bytes[MAX_STR_LEN] = 0;
+    let path = str::from_utf8(bytes).unwrap();
+    assert_eq!(Err(Errno::EFAULT), sys::spawn(path));
+    assert_eq!(0, munmap(start, PAGE_SIZE * 2));
+    println!("Test EFAULT OK!");
+    0
+}
@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, sys, syscall, Errno, MapAreaInfo};
+
+/// 各类错误应返回对应的 errno，而不是让内核 panic。
+/// 理想输出：Test errno OK!
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(Errno::check(syscall(499, [0; 3])), Err(Errno::ENOSYS));
+    assert_eq!(Errno::check(syscall(10000, [0; 3])), Err(Errno::ENOSYS));
+    assert_eq!(sys::write(5, b"hello"), Err(Errno::EBADF));
+    assert_eq!(sys::read(5, &mut [0u8; 1]), Err(Errno::EBADF));
+    assert_eq!(sys::spawn("no_such_app\0"), Err(Errno::ENOENT));
+    assert_eq!(sys::exec("no_such_app\0", &[0 as *const u8]), Err(Errno::ENOENT));
+    assert_eq!(sys::waitpid(-1, &mut 0), Err(Errno::ECHILD));
+    assert_eq!(sys::set_priority(1), Err(Errno::EINVAL));
+
+    let start: usize = 0x10000000;
+    assert_eq!(sys::mmap(start + 1, 4096, 3), Err(Errno::EINVAL));
+    assert_eq!(0, mmap(start, 4096, 3));
+    assert_eq!(sys::mmap(start, 4096, 3), Err(Errno::EEXIST));
+    assert_eq!(0, munmap(start, 4096));
+    assert_eq!(sys::munmap(start, 4096), Err(Errno::EINVAL));
+
+    let mut areas = [MapAreaInfo::default(); 1];
+    assert_eq!(sys::task_maps(usize::MAX, &mut areas), Err(Errno::ESRCH));
+    assert_eq!(sys::meminfo(12345).err(), Some(Errno::ESRCH));
+    println!("Test errno OK!");
+    0
+}
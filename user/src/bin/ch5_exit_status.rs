@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::wait::{wcoredump, wexitstatus, wifexited, wifsignaled, wtermsig, SIGILL, SIGSEGV};
+use user_lib::{exit, fork, sys_waitpid_info, yield_, ExitInfo};
+
+/// 测试 POSIX 风格的退出状态：正常退出与被内核杀死可以区分，
+/// 缺页时给出出错地址，同时旧的退出码保持不变。
+/// 理想输出：Test exit status OK!
+
+/// Run `body` in a child and return its old style exit code along with
+/// how it ended.
+fn run(body: fn() -> i32) -> (i32, ExitInfo) {
+    let pid = fork();
+    if pid == 0 {
+        exit(body());
+    }
+    let mut exit_code = 0;
+    let mut info = ExitInfo::default();
+    loop {
+        match sys_waitpid_info(pid, &mut exit_code, &mut info) {
+            ret if ret == pid => return (exit_code, info),
+            _ => {
+                yield_();
+            }
+        }
+    }
+}
+
+fn store_to_null() -> i32 {
+    unsafe {
+        (0usize as *mut u8).write_volatile(0);
+    }
+    0
+}
+
+fn privileged_instruction() -> i32 {
+    unsafe {
+        core::arch::asm!("sret");
+    }
+    0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let (code, info) = run(|| -2);
+    assert_eq!(code, -2);
+    assert!(wifexited(info.status) && !wifsignaled(info.status));
+    assert_eq!(wexitstatus(info.status), 0xfe);
+    assert_eq!((info.cause, info.addr, info.pc), (0, 0, 0));
+
+    let (code, info) = run(|| 300);
+    assert_eq!(code, 300);
+    assert_eq!(wexitstatus(info.status), 300 & 0xff);
+
+    // the same old exit code as `exit(-2)`, but a different status
+    let (code, info) = run(store_to_null);
+    assert_eq!(code, -2);
+    assert!(wifsignaled(info.status) && !wifexited(info.status));
+    assert_eq!(wtermsig(info.status), SIGSEGV);
+    assert!(wcoredump(info.status));
+    assert_eq!(info.cause, 15);
+    assert_eq!(info.addr, 0);
+    assert_ne!(info.pc, 0);
+
+    let (code, info) = run(privileged_instruction);
+    assert_eq!(code, -3);
+    assert_eq!(wtermsig(info.status), SIGILL);
+    assert_eq!(info.cause, 2);
+    assert!(info.pc >= privileged_instruction as usize);
+    println!("Test exit status OK!");
+    0
+}
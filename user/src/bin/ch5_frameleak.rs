@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, meminfo, mmap, waitpid, MemInfo};
+
+/// 反复 fork 子进程，子进程 mmap 后退出，检查物理页帧全部归还。
+/// 需要单独运行，其他进程的内存分配会干扰计数。
+/// 理想输出：Test frame leak OK!
+
+const ROUNDS: usize = 20;
+
+fn fork_and_wait() {
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(0, mmap(0x10000000, 4096 * 4, 3));
+        exit(0);
+    }
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    assert_eq!(exit_code, 0);
+}
+
+fn free_frames() -> usize {
+    let mut info = MemInfo::default();
+    assert_eq!(0, meminfo(-1, &mut info));
+    info.free_frames
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the first child may leave behind page-table frames of the kernel stack area
+    fork_and_wait();
+    let baseline = free_frames();
+    for _ in 0..ROUNDS {
+        fork_and_wait();
+    }
+    let after = free_frames();
+    assert_eq!(baseline, after, "frames leaked: {}", baseline as isize - after as isize);
+    println!("Test frame leak OK!");
+    0
+}
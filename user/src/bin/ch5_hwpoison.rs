@@ -0,0 +1,90 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::{slice, str};
+use user_lib::wait::{wexitstatus, wifexited, wifsignaled, wtermsig, SIGSEGV};
+use user_lib::{exit, fork, sys, Errno, ExitInfo, TaskInfo, MADV_HWPOISON};
+
+/// 测试已映射但访问会出错的用户页：用 MADV_HWPOISON 毒化一页后，
+/// 系统调用读写该页时内核经异常修复表恢复并返回 EFAULT，而不是 panic；
+/// 相邻页不受影响，fork 出的子进程继承被毒化的页，直接访问该页的进程被 SIGSEGV 杀死。
+/// 理想输出：Test hwpoison OK!
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x10000000;
+/// the middle one of three pages
+const POISONED: usize = START + PAGE_SIZE;
+
+fn bad(addr: usize) -> &'static mut TaskInfo {
+    unsafe { &mut *(addr as *mut TaskInfo) }
+}
+
+/// Check that every syscall touching the poisoned page fails cleanly.
+fn check_efault() {
+    assert_eq!(Err(Errno::EFAULT), sys::task_info(bad(POISONED)));
+    // only the second page of the copy faults
+    assert_eq!(Err(Errno::EFAULT), sys::task_info(bad(POISONED - 16)));
+    let buf = unsafe { slice::from_raw_parts(POISONED as *const u8, 16) };
+    assert_eq!(Err(Errno::EFAULT), sys::write(1, buf));
+    let path = unsafe { str::from_utf8_unchecked(slice::from_raw_parts(POISONED as *const u8, 1)) };
+    assert_eq!(Err(Errno::EFAULT), sys::spawn(path));
+}
+
+fn wait(pid: isize) -> ExitInfo {
+    let mut info = ExitInfo::default();
+    assert_eq!(sys::waitpid_info(pid, &mut info), Ok(pid as usize));
+    info
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    sys::mmap(START, PAGE_SIZE * 3, 3).unwrap();
+    let pages = unsafe { slice::from_raw_parts_mut(START as *mut u8, PAGE_SIZE * 3) };
+    pages.fill(0x5a);
+
+    assert_eq!(Err(Errno::EINVAL), sys::madvise(POISONED, PAGE_SIZE, 0));
+    assert_eq!(
+        Err(Errno::EINVAL),
+        sys::madvise(POISONED + 1, PAGE_SIZE, MADV_HWPOISON)
+    );
+    // not mapped
+    assert_eq!(
+        Err(Errno::EINVAL),
+        sys::madvise(0x1000, PAGE_SIZE, MADV_HWPOISON)
+    );
+    assert_eq!(Ok(()), sys::madvise(POISONED, PAGE_SIZE, MADV_HWPOISON));
+
+    check_efault();
+    // the pages around it are still there
+    assert!(pages[..PAGE_SIZE].iter().all(|&byte| byte == 0x5a));
+    assert!(pages[PAGE_SIZE * 2..].iter().all(|&byte| byte == 0x5a));
+    assert_eq!(Ok(()), sys::task_info(bad(START)));
+
+    // the page stays poisoned in a child
+    let pid = fork();
+    if pid == 0 {
+        check_efault();
+        exit(0);
+    }
+    let info = wait(pid);
+    assert!(wifexited(info.status));
+    assert_eq!(wexitstatus(info.status), 0);
+
+    // and faults when the process itself touches it
+    let pid = fork();
+    if pid == 0 {
+        let byte = unsafe { (POISONED as *const u8).read_volatile() };
+        exit(byte as i32);
+    }
+    let info = wait(pid);
+    assert!(wifsignaled(info.status));
+    assert_eq!(wtermsig(info.status), SIGSEGV);
+    assert_eq!(info.addr, POISONED);
+
+    assert_eq!(Ok(()), sys::munmap(START, PAGE_SIZE * 3));
+    println!("Test hwpoison OK!");
+    0
+}
@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::mem::size_of;
+use user_lib::ring::RING_MAX_ENTRIES;
+use user_lib::{exit, fork, get_time, sys, waitpid, ProcInfo, Ring, SYSCALL_PROC_INFO};
+
+/// 测试内核抢占：一次 ring_enter 批量执行的系统调用中途发生时钟中断时，
+/// 进程在两个提交项之间被抢占，忙等的子进程得以运行，
+/// 其 CPU 时间在同一批的完成项之间增长。需要内核的 kernel-preempt 特性（默认开启）。
+/// 理想输出：Test kernel preempt OK!
+
+/// where the batches write the state of the child
+const BUF: usize = 0x1000_0000;
+/// long enough for many ticks to land in the middle of a batch
+const SPIN_MS: isize = 2000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let deadline = get_time() + SPIN_MS;
+    let pid = fork();
+    if pid == 0 {
+        while get_time() < deadline {}
+        exit(0);
+    }
+    let len = RING_MAX_ENTRIES * size_of::<ProcInfo>();
+    sys::mmap(BUF, (len + 4095) / 4096 * 4096, 0b011).unwrap();
+    let infos = unsafe { core::slice::from_raw_parts_mut(BUF as *mut ProcInfo, RING_MAX_ENTRIES) };
+    let mut ring = Ring::setup(RING_MAX_ENTRIES).unwrap();
+    let mut preempted = false;
+    while !preempted && get_time() < deadline {
+        for info in infos.iter_mut() {
+            let args = [pid as usize, info as *mut _ as usize, 0, 0, 0, 0];
+            assert!(ring.push(SYSCALL_PROC_INFO, args, 0));
+        }
+        assert_eq!(ring.submit(), Ok(RING_MAX_ENTRIES));
+        while let Some(completion) = ring.pop() {
+            assert_eq!(completion.result, 0);
+        }
+        // the child only runs if the batch was cut short by a tick
+        preempted = infos[0].cpu_time_ns != infos[RING_MAX_ENTRIES - 1].cpu_time_ns;
+    }
+    assert!(preempted, "no tick preempted a batch");
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test kernel preempt OK!");
+    0
+}
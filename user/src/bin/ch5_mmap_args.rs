@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{munmap, sys, Errno, MAP_ANONYMOUS, MAP_FIXED, MAP_PRIVATE};
+
+/// mmap 的六个参数分别放在 a0-a5 中，每个参数出错都应被内核发现。
+/// 理想输出：Test mmap args OK!
+
+const MAP_SHARED: usize = 0x01;
+
+#[no_mangle]
+fn main() -> i32 {
+    let start: usize = 0x10000000;
+    let len: usize = 4096 * 2;
+    let prot: usize = 3;
+    let flags = MAP_PRIVATE | MAP_FIXED | MAP_ANONYMOUS;
+    // a0: start
+    assert_eq!(sys::mmap_flags(start + 1, len, prot, flags, -1, 0), Err(Errno::EINVAL));
+    // a2: prot
+    assert_eq!(sys::mmap_flags(start, len, 0, flags, -1, 0), Err(Errno::EINVAL));
+    // a3: flags
+    assert_eq!(
+        sys::mmap_flags(start, len, prot, flags | MAP_SHARED, -1, 0),
+        Err(Errno::EINVAL)
+    );
+    assert_eq!(
+        sys::mmap_flags(start, len, prot, MAP_PRIVATE, -1, 0),
+        Err(Errno::EBADF)
+    );
+    // a4: fd
+    assert_eq!(sys::mmap_flags(start, len, prot, flags, 3, 0), Err(Errno::EINVAL));
+    // a5: offset
+    assert_eq!(sys::mmap_flags(start, len, prot, flags, -1, 4096), Err(Errno::EINVAL));
+
+    assert_eq!(Ok(()), sys::mmap_flags(start, len, prot, flags, -1, 0));
+    // a1: len, the second page is covered as well
+    assert_eq!(
+        sys::mmap_flags(start + 4096, 4096, prot, flags, -1, 0),
+        Err(Errno::EEXIST)
+    );
+    for i in (start..start + len).step_by(8) {
+        unsafe { *(i as *mut usize) = i };
+    }
+    for i in (start..start + len).step_by(8) {
+        assert_eq!(unsafe { *(i as *const usize) }, i);
+    }
+    assert_eq!(0, munmap(start, len));
+    // no flags is the mmap of the labs, whatever fd and offset
+    assert_eq!(Ok(()), sys::mmap_flags(start, len, prot, 0, 3, 4096));
+    assert_eq!(0, munmap(start, len));
+    println!("Test mmap args OK!");
+    0
+}
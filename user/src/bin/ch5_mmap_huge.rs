@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    getpid, munmap, sys, task_maps, Errno, MapAreaInfo, MAP_ANONYMOUS, MAP_FIXED, MAP_HUGETLB,
+    MAP_PRIVATE,
+};
+
+/// 使用 2 MiB 大页映射一段对齐的区域并读写。
+/// 理想输出：Test mmap huge OK!
+
+const HUGE_PAGE_SIZE: usize = 0x20_0000;
+
+#[no_mangle]
+fn main() -> i32 {
+    let start: usize = 0x40000000;
+    let len: usize = HUGE_PAGE_SIZE * 2;
+    let prot: usize = 3;
+    let flags = MAP_PRIVATE | MAP_FIXED | MAP_ANONYMOUS | MAP_HUGETLB;
+    // the start of a huge mapping must be megapage aligned
+    assert_eq!(
+        sys::mmap_flags(start + 4096, len, prot, flags, -1, 0),
+        Err(Errno::EINVAL)
+    );
+    assert_eq!(Ok(()), sys::mmap_flags(start, len, prot, flags, -1, 0));
+    for i in (start..(start + len)).step_by(4096) {
+        let addr: *mut usize = i as *mut usize;
+        unsafe {
+            *addr = i;
+        }
+    }
+    for i in (start..(start + len)).step_by(4096) {
+        let addr: *mut usize = i as *mut usize;
+        unsafe {
+            assert_eq!(*addr, i);
+        }
+    }
+    let mut areas = [MapAreaInfo::default(); 16];
+    let n = task_maps(getpid() as usize, &mut areas) as usize;
+    let area = areas[..n]
+        .iter()
+        .find(|area| area.start_vpn * 4096 == start)
+        .unwrap();
+    assert_eq!(area.huge, 1);
+    assert_eq!(area.resident, len / 4096);
+    assert_eq!(0, munmap(start, len));
+    println!("Test mmap huge OK!");
+    0
+}
@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, meminfo, sys, waitpid, yield_, Errno, MemInfo};
+
+/// 子进程 A 不断 mmap 直到物理内存耗尽，应得到 ENOMEM 而不是内核 panic；
+/// 随后父进程 fork 子进程 B 时内存不足，OOM killer 应杀死占用最多的 A。
+/// 需要单独运行。
+/// 理想输出：Test OOM killer OK!
+
+/// exit code of a process killed by the OOM killer, unlike the -9 of a
+/// plain SIGKILL
+const OOM_KILLED: i32 = -12;
+const MMAP_BASE: usize = 0x10000000;
+/// fewer free frames than a new process needs for its page tables alone. The
+/// greedy child may leave a few frames free for good, as its last 4 KiB mmap
+/// may need a page table as well.
+const FORK_FRAMES: usize = 8;
+
+fn greedy() -> ! {
+    let mut start = MMAP_BASE;
+    for chunk in [0x100000, 0x10000, 0x1000] {
+        loop {
+            if let Err(errno) = sys::mmap(start, chunk, 3) {
+                assert_eq!(errno, Errno::ENOMEM);
+                break;
+            }
+            start += chunk;
+        }
+    }
+    println!("greedy child holds {:#x} bytes", start - MMAP_BASE);
+    loop {
+        yield_();
+    }
+}
+
+fn free_frames() -> usize {
+    let mut info = MemInfo::default();
+    assert_eq!(0, meminfo(-1, &mut info));
+    info.free_frames
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let greedy_pid = fork();
+    if greedy_pid == 0 {
+        greedy();
+    }
+    while free_frames() >= FORK_FRAMES {
+        yield_();
+    }
+    // not even the page tables of a child fit now
+    let pid = fork();
+    if pid == 0 {
+        exit(0);
+    }
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(greedy_pid, waitpid(greedy_pid as usize, &mut exit_code));
+    assert_eq!(exit_code, OOM_KILLED);
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    assert_eq!(exit_code, 0);
+    println!("Test OOM killer OK!");
+    0
+}
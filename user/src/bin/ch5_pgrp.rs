@@ -0,0 +1,87 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, sys, yield_, Errno};
+
+/// 测试 getppid、进程组与会话：setpgid/getpgid、setsid/getsid，
+/// 以及 waitpid 以 0 或负数 pid 等待同组的任意子进程。
+/// 理想输出：Test process groups OK!
+
+fn child(body: impl FnOnce() -> i32) -> usize {
+    let pid = fork();
+    if pid == 0 {
+        exit(body());
+    }
+    assert!(pid > 0);
+    pid as usize
+}
+
+fn reap(pid: isize) -> (usize, i32) {
+    let mut exit_code = 0;
+    let pid = sys::waitpid(pid, &mut exit_code).unwrap();
+    (pid, exit_code)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid() as usize;
+    let pgid = sys::getpgid(0).unwrap();
+    let sid = sys::getsid(0).unwrap();
+    assert_eq!(sys::getpgid(pid), Ok(pgid));
+
+    // children inherit the parent, group and session
+    let a = child(move || {
+        assert_eq!(sys::getppid(), pid);
+        assert_eq!(sys::getpgid(0), Ok(pgid));
+        assert_eq!(sys::getsid(0), Ok(sid));
+        sys::setpgid(0, 0).unwrap();
+        // a group leader can't start a session
+        assert_eq!(sys::setsid().err(), Some(Errno::EPERM));
+        for _ in 0..10 {
+            yield_();
+        }
+        1
+    });
+    // set by both sides, as a shell does
+    sys::setpgid(a, a).unwrap();
+    assert_eq!(sys::getpgid(a), Ok(a));
+    let b = child(|| 2);
+    sys::setpgid(b, a).unwrap();
+    assert_eq!(sys::getpgid(b), Ok(a));
+
+    // only existing groups of the session can be joined
+    assert_eq!(sys::setpgid(0, usize::MAX - 1).err(), Some(Errno::EPERM));
+    // only the caller and its children can be moved
+    assert_eq!(sys::setpgid(sys::getppid(), 0).err(), Some(Errno::ESRCH));
+
+    let c = child(|| 3);
+    // nothing of ours is in our own group but c
+    let (reaped, exit_code) = reap(0);
+    assert_eq!((reaped, exit_code), (c, 3));
+
+    let mut group = [reap(-(a as isize)), reap(-(a as isize))];
+    group.sort_unstable();
+    assert_eq!(group, [(a, 1), (b, 2)]);
+    let mut exit_code = 0;
+    assert_eq!(sys::waitpid(-(a as isize), &mut exit_code).err(), Some(Errno::ECHILD));
+
+    let d = child(|| {
+        let me = getpid() as usize;
+        assert_eq!(sys::setsid(), Ok(me));
+        assert_eq!(sys::getsid(0), Ok(me));
+        assert_eq!(sys::getpgid(0), Ok(me));
+        4
+    });
+    while sys::getsid(d) != Ok(d) {
+        yield_();
+    }
+    // d leads another session now
+    assert_eq!(sys::setpgid(d, pgid).err(), Some(Errno::EPERM));
+    assert_eq!(reap(d as isize), (d, 4));
+    assert_eq!(sys::getsid(d).err(), Some(Errno::ESRCH));
+    println!("Test process groups OK!");
+    0
+}
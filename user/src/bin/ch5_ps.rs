@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, sys, waitpid, yield_, Errno, ProcInfo};
+
+/// 测试进程列表与按 pid 查询：自身处于运行态，子进程的 ppid 正确，
+/// 回收后子进程消失，缓冲区不足时仍返回总数。
+/// 理想输出：Test process listing OK!
+
+const RUNNING: usize = 2;
+const READY: usize = 1;
+const ZOMBIE: usize = 3;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid() as usize;
+    let me = sys::proc_info(pid).unwrap();
+    assert_eq!(me.pid, pid);
+    assert_eq!(me.status, RUNNING);
+    assert!(me.ppid >= 0);
+
+    let child = fork();
+    if child == 0 {
+        for _ in 0..10 {
+            yield_();
+        }
+        exit(0);
+    }
+    let child = child as usize;
+    let info = sys::proc_info(child).unwrap();
+    assert_eq!(info.ppid, pid as isize);
+    assert!(info.status == READY || info.status == ZOMBIE);
+
+    let mut procs = [ProcInfo::default(); 32];
+    let total = sys::proc_list(&mut procs).unwrap();
+    let listed = &procs[..total.min(procs.len())];
+    assert!(listed.iter().any(|p| p.pid == pid));
+    assert!(listed.iter().any(|p| p.pid == child));
+    assert!(listed.windows(2).all(|w| w[0].pid < w[1].pid));
+
+    // A buffer too small still reports how many there are.
+    let mut one = [ProcInfo::default(); 1];
+    assert_eq!(sys::proc_list(&mut one).unwrap(), total);
+    assert!(total > 1);
+
+    let mut exit_code = 0;
+    assert_eq!(waitpid(child, &mut exit_code), child as isize);
+    assert_eq!(sys::proc_info(child).err(), Some(Errno::ESRCH));
+    println!("Test process listing OK!");
+    0
+}
@@ -0,0 +1,119 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+use user_lib::ptrace::{self, Breakpoint, C_EBREAK};
+use user_lib::wait::{wexitstatus, wifexited, wifstopped, wstopsig, wtermsig, SIGTRAP};
+use user_lib::{exit, fork, sys, vdso, Errno, ExitInfo};
+
+/// 测试 ptrace：被跟踪的子进程在 ebreak 处停下，父进程可读写其寄存器与内存、
+/// 单步执行、设置断点并让其继续运行，但不能改写所有进程共享的时间页；
+/// 未被跟踪的进程执行 ebreak 则被 SIGTRAP 杀死。
+/// 理想输出：Test ptrace OK!
+
+const MAGIC: usize = 0x1234;
+const CHANGED: usize = 0x5678;
+const POKED: u64 = 0xdead_beef;
+
+static mut VALUE: u64 = 0;
+
+#[inline(never)]
+fn marker() {
+    unsafe {
+        asm!("nop");
+    }
+}
+
+fn stop(pid: usize) -> ExitInfo {
+    let mut info = ExitInfo::default();
+    assert_eq!(sys::waitpid_info(pid as isize, &mut info), Ok(pid));
+    assert!(wifstopped(info.status));
+    assert_eq!(wstopsig(info.status), SIGTRAP);
+    info
+}
+
+/// The length of the `ebreak` at `pc`, which the assembler may compress.
+fn ebreak_len(pid: usize, pc: usize) -> usize {
+    if ptrace::peek(pid, pc).unwrap() as u16 == C_EBREAK {
+        2
+    } else {
+        4
+    }
+}
+
+fn traced_child() -> ! {
+    ptrace::traceme().unwrap();
+    let a0: usize;
+    unsafe {
+        asm!("ebreak", inlateout("a0") MAGIC => a0);
+    }
+    marker();
+    let ok = a0 == CHANGED && unsafe { (&VALUE as *const u64).read_volatile() } == POKED;
+    exit(if ok { 0 } else { 1 });
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // only the parent may trace, and only a traced child
+    assert_eq!(ptrace::cont(1), Err(Errno::ESRCH));
+    let pid = fork();
+    if pid == 0 {
+        traced_child();
+    }
+    let pid = pid as usize;
+    let info = stop(pid);
+    let mut regs = ptrace::getregs(pid).unwrap();
+    assert_eq!(regs.pc, info.pc);
+    assert_eq!(regs.reg(10), MAGIC);
+
+    // step past the ebreak with the registers changed
+    regs.pc += ebreak_len(pid, regs.pc);
+    regs.set_reg(10, CHANGED);
+    ptrace::setregs(pid, &regs).unwrap();
+    let addr = unsafe { &VALUE as *const u64 as usize };
+    ptrace::poke(pid, addr, POKED).unwrap();
+    assert_eq!(ptrace::peek(pid, addr), Ok(POKED));
+    assert_eq!(ptrace::peek(pid, 0), Err(Errno::EFAULT));
+    // the time page of the child is that of every process
+    let page = vdso::base().unwrap();
+    let clock = ptrace::peek(pid, page).unwrap();
+    assert_eq!(ptrace::poke(pid, page, 0), Err(Errno::EFAULT));
+    assert_eq!(unsafe { (page as *const u64).read_volatile() }, clock);
+    // the parent's copy is untouched
+    assert_eq!(unsafe { (&VALUE as *const u64).read_volatile() }, 0);
+
+    let mut pc = regs.pc;
+    for _ in 0..3 {
+        ptrace::singlestep(pid).unwrap();
+        let info = stop(pid);
+        assert_ne!(info.pc, pc);
+        pc = info.pc;
+    }
+    assert_eq!(ptrace::getregs(pid).unwrap().reg(10), CHANGED);
+
+    let breakpoint = Breakpoint::insert(pid, marker as usize).unwrap();
+    ptrace::cont(pid).unwrap();
+    assert_eq!(stop(pid).pc, marker as usize);
+    breakpoint.remove(pid).unwrap();
+    ptrace::cont(pid).unwrap();
+    let mut info = ExitInfo::default();
+    assert_eq!(sys::waitpid_info(pid as isize, &mut info), Ok(pid));
+    assert!(wifexited(info.status));
+    assert_eq!(wexitstatus(info.status), 0);
+
+    // without a tracer, a breakpoint is fatal
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            asm!("ebreak");
+        }
+        exit(0);
+    }
+    assert_eq!(sys::waitpid_info(pid, &mut info), Ok(pid as usize));
+    assert_eq!(wtermsig(info.status), SIGTRAP);
+    println!("Test ptrace OK!");
+    0
+}
@@ -0,0 +1,93 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::ring::Completion;
+use user_lib::{
+    exit, fork, getpid, sys, waitpid, Errno, Ring, TimeVal, SYSCALL_FORK, SYSCALL_GETPID,
+    SYSCALL_GETTIMEOFDAY, SYSCALL_MUNMAP, SYSCALL_WRITE,
+};
+
+/// 测试系统调用环：批量提交的系统调用按序执行并产生完成项，完成队列满时停止，
+/// fork 出的子进程拥有环的副本，环不能被 munmap。
+/// 理想输出：Test ring OK!
+
+const ENTRIES: usize = 4;
+
+fn expect(ring: &mut Ring, user_data: usize) -> isize {
+    let Completion {
+        user_data: got,
+        result,
+    } = ring.pop().expect("missing completion");
+    assert_eq!(got, user_data);
+    result
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for entries in [0, 3, 512] {
+        assert_eq!(Ring::setup(entries).err(), Some(Errno::EINVAL));
+    }
+    let mut ring = Ring::setup(ENTRIES).unwrap();
+    assert_eq!(Ring::setup(ENTRIES).err(), Some(Errno::EEXIST));
+    assert_eq!(ring.submit(), Ok(0));
+
+    let msg = b"hello from the ring\n";
+    let mut time = TimeVal::new();
+    assert!(ring.push(SYSCALL_GETPID, [0; 6], 1));
+    assert!(ring.push(SYSCALL_WRITE, [1, msg.as_ptr() as usize, msg.len(), 0, 0, 0], 2));
+    assert!(ring.push(SYSCALL_GETTIMEOFDAY, [&mut time as *mut _ as usize, 0, 0, 0, 0, 0], 3));
+    assert!(ring.push(SYSCALL_FORK, [0; 6], 4));
+    assert!(!ring.push(SYSCALL_GETPID, [0; 6], 5));
+    assert_eq!(ring.pending(), ENTRIES);
+    assert_eq!(ring.submit(), Ok(ENTRIES));
+    assert_eq!(expect(&mut ring, 1), getpid());
+    assert_eq!(expect(&mut ring, 2), msg.len() as isize);
+    assert_eq!(expect(&mut ring, 3), 0);
+    assert!(time.sec > 0 || time.usec > 0);
+    assert_eq!(expect(&mut ring, 4), -(Errno::EINVAL.code() as isize));
+    assert!(ring.pop().is_none());
+
+    // a bad pointer fails the entry, not the batch
+    assert!(ring.push(SYSCALL_WRITE, [1, 0x1000, 16, 0, 0, 0], 6));
+    assert_eq!(ring.submit(), Ok(1));
+    assert_eq!(expect(&mut ring, 6), -(Errno::EFAULT.code() as isize));
+
+    // the ring can't be unmapped, directly or from the ring itself
+    assert_eq!(sys::munmap(ring.base(), 4096), Err(Errno::EINVAL));
+    assert!(ring.push(SYSCALL_MUNMAP, [ring.base(), 4096, 0, 0, 0, 0], 7));
+    assert_eq!(ring.submit(), Ok(1));
+    assert_eq!(expect(&mut ring, 7), -(Errno::EINVAL.code() as isize));
+
+    // nothing runs while the completion queue is full
+    for i in 0..ENTRIES {
+        assert!(ring.push(SYSCALL_GETPID, [0; 6], 10 + i));
+    }
+    assert_eq!(ring.submit(), Ok(ENTRIES));
+    assert!(ring.push(SYSCALL_GETPID, [0; 6], 20));
+    assert_eq!(ring.submit(), Ok(0));
+    assert_eq!(expect(&mut ring, 10), getpid());
+    assert_eq!(ring.submit(), Ok(1));
+    for i in 1..ENTRIES {
+        assert_eq!(expect(&mut ring, 10 + i), getpid());
+    }
+    assert_eq!(expect(&mut ring, 20), getpid());
+
+    // the child gets a copy of its own
+    let pid = fork();
+    if pid == 0 {
+        assert!(ring.push(SYSCALL_GETPID, [0; 6], 30));
+        assert_eq!(ring.submit(), Ok(1));
+        assert_eq!(expect(&mut ring, 30), getpid());
+        exit(0);
+    }
+    let mut exit_code = -1;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    assert_eq!(exit_code, 0);
+    assert_eq!(ring.pending(), 0);
+    assert!(ring.pop().is_none());
+    println!("Test ring OK!");
+    0
+}
@@ -0,0 +1,137 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getpid, getrlimit, meminfo, mmap, munmap, setrlimit, spawn, sys, task_maps, wait,
+    waitpid, Errno, MapAreaInfo, MemInfo, RLimit, RLIMIT_AS, RLIMIT_CPU, RLIMIT_NPROC, RLIMIT_RSS,
+    RLIMIT_STACK, RLIM_INFINITY,
+};
+
+/// 测试资源限制：进程数（含孙进程）、地址空间、常驻内存、CPU 时间与栈大小。
+/// 理想输出：Test rlimit OK!
+
+/// exit code of a process out of CPU time
+const CPU_LIMIT_EXIT: i32 = -24;
+const PAGE_SIZE: usize = 4096;
+const MMAP_BASE: usize = 0x10000000;
+
+fn limit(cur: usize, max: usize) -> RLimit {
+    RLimit { cur, max }
+}
+
+/// Processes in the system, reaped or not.
+fn process_count() -> usize {
+    sys::proc_list(&mut []).unwrap()
+}
+
+fn test_nproc() {
+    let mut rlim = RLimit::default();
+    assert_eq!(0, getrlimit(RLIMIT_NPROC, &mut rlim));
+    assert_eq!(rlim.cur, RLIM_INFINITY);
+    assert_eq!(0, setrlimit(RLIMIT_NPROC, &limit(process_count() + 2, RLIM_INFINITY)));
+    for _ in 0..2 {
+        if fork() == 0 {
+            exit(0);
+        }
+    }
+    assert_eq!(sys::fork(), Err(Errno::EAGAIN));
+    let mut exit_code = 0;
+    for _ in 0..2 {
+        assert!(wait(&mut exit_code) > 0);
+    }
+    // reaped children no longer count, but grandchildren do
+    let pid = fork();
+    if pid == 0 {
+        let grandchild = fork();
+        if grandchild == 0 {
+            assert_eq!(sys::fork(), Err(Errno::EAGAIN));
+            exit(0);
+        }
+        assert!(grandchild > 0);
+        assert_eq!(sys::fork(), Err(Errno::EAGAIN));
+        assert_eq!(grandchild, waitpid(grandchild as usize, &mut exit_code));
+        exit(exit_code);
+    }
+    assert!(pid > 0);
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    assert_eq!(exit_code, 0);
+    assert_eq!(0, setrlimit(RLIMIT_NPROC, &limit(RLIM_INFINITY, RLIM_INFINITY)));
+}
+
+fn test_hard_limit() {
+    assert_eq!(sys::setrlimit(RLIMIT_NPROC, &limit(8, 4)), Err(Errno::EINVAL));
+    assert_eq!(0, setrlimit(RLIMIT_NPROC, &limit(4, 8)));
+    // the hard limit can't be raised again
+    assert_eq!(sys::setrlimit(RLIMIT_NPROC, &limit(4, 16)), Err(Errno::EPERM));
+    assert_eq!(0, setrlimit(RLIMIT_NPROC, &limit(8, 8)));
+    assert_eq!(-1, setrlimit(100, &limit(0, 0)));
+}
+
+fn mapped_pages() -> usize {
+    let mut areas = [MapAreaInfo::default(); 32];
+    let n = task_maps(getpid() as usize, &mut areas) as usize;
+    areas[..n].iter().map(|a| a.end_vpn - a.start_vpn).sum()
+}
+
+fn test_address_space() {
+    let size = (mapped_pages() + 2) * PAGE_SIZE;
+    assert_eq!(0, setrlimit(RLIMIT_AS, &limit(size, RLIM_INFINITY)));
+    assert_eq!(0, mmap(MMAP_BASE, PAGE_SIZE * 2, 3));
+    assert_eq!(
+        sys::mmap(MMAP_BASE + PAGE_SIZE * 2, PAGE_SIZE, 3),
+        Err(Errno::ENOMEM)
+    );
+    assert_eq!(0, munmap(MMAP_BASE, PAGE_SIZE * 2));
+    assert_eq!(0, setrlimit(RLIMIT_AS, &limit(RLIM_INFINITY, RLIM_INFINITY)));
+}
+
+fn test_resident() {
+    let mut info = MemInfo::default();
+    assert_eq!(0, meminfo(-1, &mut info));
+    let size = (info.rss + 2) * PAGE_SIZE;
+    assert_eq!(0, setrlimit(RLIMIT_RSS, &limit(size, RLIM_INFINITY)));
+    assert_eq!(sys::mmap(MMAP_BASE, PAGE_SIZE * 3, 3), Err(Errno::ENOMEM));
+    assert_eq!(0, mmap(MMAP_BASE, PAGE_SIZE * 2, 3));
+    assert_eq!(0, munmap(MMAP_BASE, PAGE_SIZE * 2));
+    assert_eq!(0, setrlimit(RLIMIT_RSS, &limit(RLIM_INFINITY, RLIM_INFINITY)));
+}
+
+fn test_cpu() {
+    assert_eq!(0, setrlimit(RLIMIT_CPU, &limit(1, RLIM_INFINITY)));
+    let pid = fork();
+    if pid == 0 {
+        // inherited from the parent
+        #[allow(clippy::empty_loop)]
+        loop {}
+    }
+    assert_eq!(0, setrlimit(RLIMIT_CPU, &limit(RLIM_INFINITY, RLIM_INFINITY)));
+    let mut exit_code = 0;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    assert_eq!(exit_code, CPU_LIMIT_EXIT);
+}
+
+fn test_stack() {
+    // a stack limit below a page still leaves a page to start with
+    assert_eq!(0, setrlimit(RLIMIT_STACK, &limit(100, RLIM_INFINITY)));
+    let pid = spawn("ch5_exit0\0");
+    assert!(pid > 0);
+    assert_eq!(0, setrlimit(RLIMIT_STACK, &limit(RLIM_INFINITY, RLIM_INFINITY)));
+    let mut exit_code = 0;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    assert_eq!(exit_code, 66778);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    test_nproc();
+    test_hard_limit();
+    test_address_space();
+    test_resident();
+    test_cpu();
+    test_stack();
+    println!("Test rlimit OK!");
+    0
+}
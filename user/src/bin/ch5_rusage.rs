@@ -0,0 +1,100 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, getpid, sys, waitpid, yield_, Errno, RUsage, CLK_TCK, RUSAGE_CHILDREN,
+    RUSAGE_SELF,
+};
+
+/// 测试资源使用统计：用户态/内核态时间、主动与被动切换次数、缺页次数，
+/// 以及 waitpid 时累加的子进程统计。
+/// 理想输出：Test rusage OK!
+
+const US_PER_TICK: usize = 1_000_000 / CLK_TCK;
+
+fn usage(who: isize) -> RUsage {
+    sys::getrusage(who).unwrap()
+}
+
+/// Spin in user mode, reading the clock from the time page.
+fn spin(ms: isize) {
+    let start = get_time();
+    while get_time() < start + ms {}
+}
+
+fn fault() -> ! {
+    unsafe {
+        (0usize as *mut u8).write_volatile(0);
+    }
+    unreachable!();
+}
+
+fn reap(pid: isize, expected: i32) {
+    let mut exit_code = 0;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    assert_eq!(exit_code, expected);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(sys::getrusage(5).err(), Some(Errno::EINVAL));
+
+    let before = usage(RUSAGE_SELF);
+    for _ in 0..10 {
+        yield_();
+    }
+    let after = usage(RUSAGE_SELF);
+    assert!(after.voluntary_switches >= before.voluntary_switches + 10);
+
+    // the timer preempts a spinning task
+    let before = after;
+    spin(60);
+    let after = usage(RUSAGE_SELF);
+    assert!(after.involuntary_switches > before.involuntary_switches);
+    assert!(after.utime.as_us() >= before.utime.as_us() + 10_000);
+
+    let before = after;
+    for _ in 0..1000 {
+        getpid();
+    }
+    let after = usage(RUSAGE_SELF);
+    assert!(after.stime.as_us() > before.stime.as_us());
+    assert_eq!(after.page_faults, 0);
+
+    let children = usage(RUSAGE_CHILDREN);
+    assert_eq!(children.utime.as_us(), 0);
+    assert_eq!(children.page_faults, 0);
+
+    // grandchildren count once their parent has waited for them
+    let pid = fork();
+    if pid == 0 {
+        spin(30);
+        let pid = fork();
+        if pid == 0 {
+            fault();
+        }
+        reap(pid, -2);
+        exit(0);
+    }
+    reap(pid, 0);
+    let pid = fork();
+    if pid == 0 {
+        fault();
+    }
+    reap(pid, -2);
+    let children = usage(RUSAGE_CHILDREN);
+    assert_eq!(children.page_faults, 2);
+    assert!(children.utime.as_us() >= 10_000);
+    assert!(children.involuntary_switches > 0);
+
+    let (tms, ticks) = sys::times().unwrap();
+    assert!(ticks > 0);
+    assert_eq!(tms.cutime, children.utime.as_us() / US_PER_TICK);
+    assert_eq!(tms.cstime, children.stime.as_us() / US_PER_TICK);
+    assert!(tms.utime >= after.utime.as_us() / US_PER_TICK);
+    println!("Test rusage OK!");
+    0
+}
@@ -0,0 +1,101 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::seccomp::SECCOMP_KILLED_EXIT_CODE;
+use user_lib::{
+    exit, fork, getpid, sys, waitpid, ArgOp, Errno, SeccompFilter, RLIMIT_CPU, SYSCALL_FORK,
+    SYSCALL_GETPID, SYSCALL_MMAP, SYSCALL_MUNMAP, SYSCALL_SECCOMP, SYSCALL_SPAWN, SYSCALL_WAITPID,
+    SYSCALL_WRITE, SYSCALL_YIELD,
+};
+
+/// 测试系统调用过滤：被拒绝的调用返回 EPERM 或杀死进程，参数谓词生效，
+/// 过滤器由子进程继承且只能收紧。
+/// 理想输出：Test seccomp OK!
+
+const PAGE_SIZE: usize = 4096;
+const PROT_EXEC: usize = 0b100;
+
+/// Run `f` in a child, so its filter does not outlive it, and return its
+/// exit code.
+fn sandboxed(f: fn() -> i32) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f());
+    }
+    let mut exit_code = 0;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    exit_code
+}
+
+fn base_filter() -> SeccompFilter {
+    SeccompFilter::new()
+        .allow(SYSCALL_WRITE)
+        .allow(SYSCALL_YIELD)
+        .allow(SYSCALL_GETPID)
+        .allow(SYSCALL_FORK)
+        .allow(SYSCALL_WAITPID)
+        .allow(SYSCALL_MUNMAP)
+        .allow(SYSCALL_SECCOMP)
+        .allow_if(SYSCALL_MMAP, 2, ArgOp::MaskedZero, PROT_EXEC)
+}
+
+fn restricted() -> i32 {
+    assert_eq!(Ok(()), base_filter().install());
+    assert_eq!(Err(Errno::EPERM), sys::spawn("ch2b_hello_world\0"));
+    assert_eq!(Err(Errno::EPERM), sys::getrlimit(RLIMIT_CPU).map(|_| ()));
+    let start = 0x10000000;
+    assert_eq!(Err(Errno::EPERM), sys::mmap(start, PAGE_SIZE, 0b111));
+    assert_eq!(Ok(()), sys::mmap(start, PAGE_SIZE, 0b011));
+    assert_eq!(Ok(()), sys::munmap(start, PAGE_SIZE));
+
+    // the child inherits the filter and tightens it further
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(Err(Errno::EPERM), sys::spawn("ch2b_hello_world\0"));
+        let tighter = SeccompFilter::new()
+            .allow(SYSCALL_WRITE)
+            .allow(SYSCALL_GETPID)
+            .allow(SYSCALL_SECCOMP)
+            .allow(SYSCALL_SPAWN);
+        assert_eq!(Ok(()), tighter.install());
+        assert!(getpid() > 0);
+        assert_eq!(Err(Errno::EPERM), sys::fork());
+        // allowing it again does not help
+        assert_eq!(Err(Errno::EPERM), sys::spawn("ch2b_hello_world\0"));
+        assert_eq!(Err(Errno::EPERM), sys::mmap(start, PAGE_SIZE, 0b011));
+        exit(3);
+    }
+    let mut exit_code = 0;
+    assert_eq!(Ok(pid as usize), sys::waitpid(pid, &mut exit_code));
+    assert_eq!(exit_code, 3);
+    0
+}
+
+fn killed() -> i32 {
+    let filter = SeccompFilter::new().allow(SYSCALL_WRITE).kill();
+    assert_eq!(Ok(()), filter.install());
+    println!("calling getpid, should be killed");
+    getpid();
+    panic!("getpid returned");
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // invalid filters are rejected without being installed
+    let bad_arg = SeccompFilter::new().allow_if(SYSCALL_MMAP, 6, ArgOp::Eq, 0);
+    assert_eq!(Err(Errno::EINVAL), bad_arg.install());
+    let mut too_many = SeccompFilter::new();
+    for i in 0..33 {
+        too_many = too_many.allow_if(SYSCALL_MMAP, 0, ArgOp::Ne, i);
+    }
+    assert_eq!(Err(Errno::EINVAL), too_many.install());
+    assert!(sys::getrlimit(RLIMIT_CPU).is_ok());
+
+    assert_eq!(0, sandboxed(restricted));
+    assert_eq!(SECCOMP_KILLED_EXIT_CODE, sandboxed(killed));
+    println!("Test seccomp OK!");
+    0
+}
@@ -0,0 +1,78 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, spawn, sys, trace, waitpid, yield_, Errno, TraceEntry};
+
+/// 测试系统调用跟踪：开启跟踪后创建的子进程继承跟踪，父进程可以读出其记录。
+/// 理想输出：Test trace OK!
+
+const SYSCALL_WRITE: usize = 64;
+const SYSCALL_EXIT: usize = 93;
+const SYSCALL_GETPID: usize = 172;
+
+/// Read the whole log of child `pid`, which must exit, into `log`.
+fn read_all(pid: isize, log: &mut [TraceEntry]) -> usize {
+    let mut n = 0;
+    loop {
+        match sys::trace_read(pid, &mut log[n..]) {
+            Ok(0) => {
+                yield_();
+            }
+            Ok(m) => n += m,
+            Err(errno) => {
+                assert_eq!(errno, Errno::ESRCH);
+                return n;
+            }
+        }
+        assert!(n < log.len(), "log too long");
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut entries = [TraceEntry::default(); 4];
+    assert_eq!(sys::trace_read(-1, &mut entries), Err(Errno::EINVAL));
+    assert_eq!(sys::trace(getpid() as isize + 1000, true), Err(Errno::ESRCH));
+
+    // spawn inherits tracing
+    assert_eq!(0, trace(-1, true));
+    let pid = spawn("ch2b_hello_world\0");
+    assert_eq!(0, trace(-1, false));
+    assert!(pid > 0);
+    let mut log = [TraceEntry::default(); 64];
+    let n = read_all(pid, &mut log);
+    let mut exit_code = 0;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    let write = log[..n]
+        .iter()
+        .find(|e| e.id == SYSCALL_WRITE)
+        .expect("no write logged");
+    assert_eq!(write.args[0], 1);
+    assert_eq!(write.finished, 1);
+    assert_eq!(write.ret, write.args[2] as isize);
+    let last = &log[n - 1];
+    assert_eq!(last.id, SYSCALL_EXIT);
+    assert_eq!(last.args[0], exit_code as usize);
+    assert_eq!(last.finished, 0);
+    assert!(log[..n].windows(2).all(|w| w[0].start_us <= w[1].start_us));
+
+    // so does fork
+    assert_eq!(0, trace(-1, true));
+    let pid = fork();
+    if pid == 0 {
+        getpid();
+        exit(7);
+    }
+    assert_eq!(0, trace(-1, false));
+    let n = read_all(pid, &mut log);
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    assert_eq!(exit_code, 7);
+    assert!(log[..n]
+        .iter()
+        .any(|e| e.id == SYSCALL_GETPID && e.ret == pid));
+    println!("Test trace OK!");
+    0
+}
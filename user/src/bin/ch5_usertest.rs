@@ -1,6 +1,8 @@
 #![no_std]
 #![no_main]
 
+extern crate alloc;
+
 #[macro_use]
 extern crate user_lib;
 
@@ -20,24 +22,54 @@ static TESTS: &[&str] = &[
     "ch4_mmap3\0",
     "ch4_unmap\0",
     "ch4_unmap2\0",
+    "ch5_mmap_huge\0",
+    "ch5_mmap_args\0",
     "ch5_spawn0\0",
     "ch5_spawn1\0",
     "ch5_setprio\0",
+    "ch5_rlimit\0",
+    "ch5_efault\0",
+    "ch5_hwpoison\0",
+    "ch5_errno\0",
+    "ch5_trace\0",
+    "ch5_seccomp\0",
+    "ch5_ring\0",
+    "ch5_vdso\0",
+    "ch5_clock\0",
+    "ch5_rusage\0",
+    "ch5_ps\0",
+    "ch5_comm\0",
+    "ch5_pgrp\0",
+    "ch5_exit_status\0",
+    "ch5_coredump\0",
+    "ch5_ptrace\0",
+    "ch5_backtrace\0",
+    "ch5_kpreempt\0",
     // "ch5_stride\0",
 ];
-static STEST: &str = "ch5_stride\0";
+/// 以下测例需要在其他测例结束后依次单独运行
+static SERIAL_TESTS: &[&str] = &[
+    "ch5_stride\0",
+    // 统计全局空闲页帧
+    "ch5_frameleak\0",
+    // 耗尽物理内存
+    "ch5_oom\0",
+];
 
+use alloc::vec::Vec;
 use user_lib::{spawn, waitpid};
 
 /// 辅助测例，运行所有其他测例。
 
 #[no_mangle]
 pub fn main() -> i32 {
-    let mut pid = [0; 20];
-    for (i, &test) in TESTS.iter().enumerate() {
-        println!("Usertests: Running {}", test);
-        pid[i] = spawn(test);
-    }
+    let pid: Vec<isize> = TESTS
+        .iter()
+        .map(|&test| {
+            println!("Usertests: Running {}", test);
+            spawn(test)
+        })
+        .collect();
     let mut xstate: i32 = Default::default();
     for (i, &test) in TESTS.iter().enumerate() {
         let wait_pid = waitpid(pid[i] as usize, &mut xstate);
@@ -47,15 +79,17 @@ pub fn main() -> i32 {
             test, pid[i], xstate
         );
     }
-    println!("Usertests: Running {}", STEST);
-    let spid = spawn(STEST);
-    xstate = Default::default();
-    let wait_pid = waitpid(spid as usize, &mut xstate);
-    assert_eq!(spid, wait_pid);
-    println!(
-        "\x1b[32mUsertests: Test {} in Process {} exited with code {}\x1b[0m",
-        STEST, spid, xstate
-    );
+    for &test in SERIAL_TESTS.iter() {
+        println!("Usertests: Running {}", test);
+        let spid = spawn(test);
+        xstate = Default::default();
+        let wait_pid = waitpid(spid as usize, &mut xstate);
+        assert_eq!(spid, wait_pid);
+        println!(
+            "\x1b[32mUsertests: Test {} in Process {} exited with code {}\x1b[0m",
+            test, spid, xstate
+        );
+    }
     println!("ch5 Usertests passed!");
     0
 }
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, get_time_trap, sys, vdso, waitpid, TaskInfo, SYSCALL_GETTIMEOFDAY,
+};
+
+/// 测试共享时间页：get_time 不再陷入内核，读数与系统调用一致，且时间页只读。
+/// 理想输出：Test vDSO OK!
+
+fn get_time_calls() -> u32 {
+    let mut info = TaskInfo::new();
+    sys::task_info(&mut info).unwrap();
+    info.syscall_times[SYSCALL_GETTIMEOFDAY]
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert!(vdso::time_us().is_some());
+    let calls = get_time_calls();
+    let mut last = get_time();
+    for _ in 0..1000 {
+        let now = get_time();
+        assert!(now >= last);
+        last = now;
+    }
+    assert_eq!(calls, get_time_calls());
+
+    for _ in 0..10 {
+        let before = get_time_trap();
+        let now = get_time();
+        let after = get_time_trap();
+        assert!(before <= now && now <= after);
+    }
+
+    // the page can be read but not written
+    let page = vdso::base().unwrap();
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            assert!((page as *const usize).read_volatile() > 0);
+            (page as *mut usize).write_volatile(0);
+        }
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    assert_eq!(exit_code, -2);
+    println!("Test vDSO OK!");
+    0
+}
@@ -0,0 +1,83 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+#[macro_use]
+extern crate user_lib;
+
+const LF: u8 = 0x0au8;
+const CR: u8 = 0x0du8;
+
+use alloc::string::String;
+use user_lib::console::getchar;
+use user_lib::wait::wcoredump;
+use user_lib::{flush, spawn, sys, ExitInfo};
+
+/// 读入程序名并运行它；若其因异常退出并留下 core dump，则以十六进制打印出来，
+/// 供宿主机上的 os5/scripts/extract_core.py 还原为 ELF core 文件，用 gdb 加载。
+
+const CHUNK: usize = 4096;
+/// bytes per line of the hex dump
+const LINE: usize = 32;
+
+fn read_line() -> String {
+    let mut line = String::new();
+    loop {
+        let c = getchar();
+        match c {
+            LF | CR => {
+                println!("");
+                return line;
+            }
+            _ => {
+                print!("{}", c as char);
+                flush();
+                line.push(c as char);
+            }
+        }
+    }
+}
+
+fn print_core(pid: usize, name: &str) {
+    let mut buf = [0u8; CHUNK];
+    let mut offset = 0;
+    println!("-----BEGIN CORE {} {}-----", pid, name);
+    loop {
+        let size = sys::coredump(pid, offset, &mut buf).unwrap();
+        let len = (size - offset).min(CHUNK);
+        for line in buf[..len].chunks(LINE) {
+            for byte in line {
+                print!("{:02x}", byte);
+            }
+            println!("");
+        }
+        offset += len;
+        if offset == size {
+            break;
+        }
+    }
+    println!("-----END CORE-----");
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    print!("coredump: program to run: ");
+    flush();
+    let name = read_line();
+    let mut path = name.clone();
+    path.push('\0');
+    let pid = spawn(path.as_str());
+    if pid < 0 {
+        println!("coredump: can't run {}", name);
+        return -1;
+    }
+    let pid = pid as usize;
+    let mut info = ExitInfo::default();
+    sys::waitpid_info(pid as isize, &mut info).unwrap();
+    println!("coredump: {} {}", name, info);
+    if wcoredump(info.status) {
+        print_core(pid, name.as_str());
+    }
+    0
+}
@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, waitpid, yield_};
+
+/// 两个进程互相 yield，测量一次进程切换（含地址空间切换）的平均开销。
+/// 在修改前后的内核上分别运行以比较 TLB 刷新策略的影响。
+
+const ROUNDS: usize = 10000;
+
+fn yield_loop() {
+    for _ in 0..ROUNDS {
+        yield_();
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = get_time();
+    let pid = fork();
+    if pid == 0 {
+        yield_loop();
+        exit(0);
+    }
+    yield_loop();
+    let mut exit_code: i32 = 0;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    let elapsed_ms = (get_time() - start) as usize;
+    let switches = ROUNDS * 2;
+    println!(
+        "{} switches in {} ms, {} ns per switch",
+        switches,
+        elapsed_ms,
+        elapsed_ms * 1_000_000 / switches
+    );
+    0
+}
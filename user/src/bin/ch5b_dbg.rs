@@ -0,0 +1,180 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+#[macro_use]
+extern crate user_lib;
+
+const LF: u8 = 0x0au8;
+const CR: u8 = 0x0du8;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use user_lib::console::getchar;
+use user_lib::ptrace::{self, Breakpoint, UserRegs};
+use user_lib::wait::wifstopped;
+use user_lib::{exec, exit, flush, fork, sys, ExitInfo};
+
+/// 一个极简的调试器：在 ptrace 下运行程序，停在入口处，随后可设置断点、
+/// 单步、继续运行并查看寄存器与内存。
+/// 命令：b <地址> 断点，s 单步，c 继续，r 寄存器，x <地址> 查看内存，q 退出。
+
+const REG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+fn read_line() -> String {
+    let mut line = String::new();
+    loop {
+        let c = getchar();
+        match c {
+            LF | CR => {
+                println!("");
+                return line;
+            }
+            _ => {
+                print!("{}", c as char);
+                flush();
+                line.push(c as char);
+            }
+        }
+    }
+}
+
+fn parse_addr(arg: Option<&str>) -> Option<usize> {
+    let arg = arg?;
+    usize::from_str_radix(arg.trim_start_matches("0x"), 16).ok()
+}
+
+struct Debugger {
+    pid: usize,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl Debugger {
+    /// Wait for the next stop, return false once the program has ended.
+    fn wait(&self) -> bool {
+        let mut info = ExitInfo::default();
+        sys::waitpid_info(self.pid as isize, &mut info).unwrap();
+        println!("dbg: {}", info);
+        wifstopped(info.status)
+    }
+    /// Run the instruction at the pc, stepping over a breakpoint there.
+    fn singlestep(&self) -> ExitInfo {
+        let pc = ptrace::getregs(self.pid).unwrap().pc;
+        let hit = self.breakpoints.iter().find(|bp| bp.addr == pc);
+        if let Some(bp) = hit {
+            bp.remove(self.pid).unwrap();
+        }
+        ptrace::singlestep(self.pid).unwrap();
+        let mut info = ExitInfo::default();
+        sys::waitpid_info(self.pid as isize, &mut info).unwrap();
+        if let (Some(bp), true) = (hit, wifstopped(info.status)) {
+            // planted anew at the same place
+            Breakpoint::insert(self.pid, bp.addr).unwrap();
+        }
+        info
+    }
+    fn step(&self) -> bool {
+        let info = self.singlestep();
+        println!("dbg: {}", info);
+        wifstopped(info.status)
+    }
+    fn cont(&self) -> bool {
+        let pc = ptrace::getregs(self.pid).unwrap().pc;
+        if self.breakpoints.iter().any(|bp| bp.addr == pc) {
+            let info = self.singlestep();
+            if !wifstopped(info.status) {
+                println!("dbg: {}", info);
+                return false;
+            }
+        }
+        ptrace::cont(self.pid).unwrap();
+        self.wait()
+    }
+    fn print_regs(&self) {
+        let regs: UserRegs = ptrace::getregs(self.pid).unwrap();
+        println!("pc   = {:#018x}", regs.pc);
+        for (n, name) in REG_NAMES.iter().enumerate().skip(1) {
+            print!("{:4} = {:#018x}", name, regs.reg(n));
+            if n % 2 == 0 {
+                println!("");
+            } else {
+                print!("  ");
+            }
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    print!("dbg: program to run: ");
+    flush();
+    let name = read_line();
+    let mut path = name.clone();
+    path.push('\0');
+    let pid = fork();
+    if pid == 0 {
+        ptrace::traceme().unwrap();
+        exec(path.as_str(), &[core::ptr::null()]);
+        println!("dbg: can't run {}", name);
+        exit(-1);
+    }
+    let mut dbg = Debugger {
+        pid: pid as usize,
+        breakpoints: Vec::new(),
+    };
+    // stopped on entering the program, unless exec failed
+    if !dbg.wait() {
+        return -1;
+    }
+    loop {
+        print!("(dbg) ");
+        flush();
+        let line = read_line();
+        let mut words = line.split_whitespace();
+        let running = match words.next() {
+            Some("b") => {
+                match parse_addr(words.next()).map(|addr| Breakpoint::insert(dbg.pid, addr)) {
+                    Some(Ok(bp)) => {
+                        println!("dbg: breakpoint at {:#x}", bp.addr);
+                        dbg.breakpoints.push(bp);
+                    }
+                    Some(Err(errno)) => println!("dbg: {:?}", errno),
+                    None => println!("dbg: usage: b <hex address>"),
+                }
+                true
+            }
+            Some("s") => dbg.step(),
+            Some("c") => dbg.cont(),
+            Some("r") => {
+                dbg.print_regs();
+                true
+            }
+            Some("x") => {
+                match parse_addr(words.next()).map(|addr| (addr, ptrace::peek(dbg.pid, addr))) {
+                    Some((addr, Ok(word))) => println!("{:#x}: {:#018x}", addr, word),
+                    Some((_, Err(errno))) => println!("dbg: {:?}", errno),
+                    None => println!("dbg: usage: x <hex address>"),
+                }
+                true
+            }
+            Some("q") => {
+                ptrace::kill(dbg.pid).unwrap();
+                dbg.wait();
+                false
+            }
+            Some(_) => {
+                println!("dbg: commands are b, s, c, r, x and q");
+                true
+            }
+            None => true,
+        };
+        if !running {
+            return 0;
+        }
+    }
+}
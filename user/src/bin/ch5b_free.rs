@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{meminfo, MemInfo};
+
+/// 打印物理页帧与内核堆的使用情况，以及各进程的常驻页数，类似 free。
+
+const PAGE_KIB: usize = 4;
+const MAX_PID: isize = 64;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut info = MemInfo::default();
+    assert_eq!(0, meminfo(-1, &mut info));
+    println!("{:>8} {:>10} {:>10} {:>10}", "", "total", "used", "free");
+    println!(
+        "{:>8} {:>9}K {:>9}K {:>9}K",
+        "Mem:",
+        info.total_frames * PAGE_KIB,
+        (info.total_frames - info.free_frames) * PAGE_KIB,
+        info.free_frames * PAGE_KIB,
+    );
+    println!(
+        "{:>8} {:>9}K {:>9}K {:>9}K",
+        "KHeap:",
+        info.heap_total / 1024,
+        info.heap_used / 1024,
+        (info.heap_total - info.heap_used) / 1024,
+    );
+    println!("{:>8} {:>10}", "PID", "RSS");
+    for pid in 0..MAX_PID {
+        if meminfo(pid, &mut info) == 0 {
+            println!("{:>8} {:>9}K", pid, info.rss * PAGE_KIB);
+        }
+    }
+    0
+}
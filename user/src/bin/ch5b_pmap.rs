@@ -0,0 +1,27 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{getpid, mmap, munmap, print_maps};
+
+/// 打印自身与 initproc 的地址空间布局，类似 /proc/<pid>/maps。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid() as usize;
+    println!("[pmap] process {}:", pid);
+    assert!(print_maps(pid) > 0);
+
+    let start: usize = 0x10000000;
+    let len: usize = 4096 * 3;
+    assert_eq!(0, mmap(start, len, 3));
+    println!("[pmap] process {} after mmap({:#x}, {:#x}):", pid, start, len);
+    assert!(print_maps(pid) > 0);
+    assert_eq!(0, munmap(start, len));
+
+    println!("[pmap] initproc:");
+    assert!(print_maps(0) > 0);
+    0
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{sys, ProcInfo};
+
+/// 列出所有尚未回收的进程，类似 ps。
+
+const MAX_PROCS: usize = 64;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut procs = [ProcInfo::default(); MAX_PROCS];
+    let total = sys::proc_list(&mut procs).unwrap();
+    println!(
+        "{:>5} {:>5} {:>5} {:>5} {:<8} {:>5} {:>10} {:>10} {}",
+        "PID", "PPID", "PGID", "SID", "STAT", "PRIO", "STRIDE", "TIME(ms)", "NAME"
+    );
+    for p in &procs[..total.min(MAX_PROCS)] {
+        println!(
+            "{:>5} {:>5} {:>5} {:>5} {:<8} {:>5} {:>10} {:>10} {}",
+            p.pid,
+            p.ppid,
+            p.pgid,
+            p.sid,
+            p.status_name(),
+            p.priority,
+            p.stride,
+            p.cpu_time_ns / 1_000_000,
+            p.name()
+        );
+    }
+    if total > MAX_PROCS {
+        println!("... {} more", total - MAX_PROCS);
+    }
+    0
+}
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::ring::RING_MAX_ENTRIES;
+use user_lib::{get_time, getpid, Ring, SYSCALL_GETPID};
+
+/// 比较逐个陷入内核与通过系统调用环批量提交的开销：
+/// 分别执行同样多次 getpid，打印每次调用的平均耗时。
+
+const CALLS: usize = 100000;
+
+fn report(what: &str, elapsed_ms: usize) {
+    println!(
+        "{}: {} calls in {} ms, {} ns per call",
+        what,
+        CALLS,
+        elapsed_ms,
+        elapsed_ms * 1_000_000 / CALLS
+    );
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = get_time();
+    for _ in 0..CALLS {
+        getpid();
+    }
+    report("trap per call", (get_time() - start) as usize);
+
+    let mut ring = Ring::setup(RING_MAX_ENTRIES).unwrap();
+    let pid = getpid();
+    let start = get_time();
+    let mut done = 0;
+    while done < CALLS {
+        let batch = RING_MAX_ENTRIES.min(CALLS - done);
+        for i in 0..batch {
+            ring.push(SYSCALL_GETPID, [0; 6], done + i);
+        }
+        assert_eq!(ring.submit(), Ok(batch));
+        while let Some(completion) = ring.pop() {
+            assert_eq!(completion.result, pid);
+        }
+        done += batch;
+    }
+    report("one trap per batch", (get_time() - start) as usize);
+    println!("batches of {} calls", RING_MAX_ENTRIES);
+    0
+}
@@ -0,0 +1,149 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+#[macro_use]
+extern crate user_lib;
+
+const LF: u8 = 0x0au8;
+const CR: u8 = 0x0du8;
+
+use alloc::string::String;
+use user_lib::console::getchar;
+use user_lib::{flush, spawn, sys, trace, waitpid, yield_, Errno, TraceEntry};
+
+/// 读入程序名，在开启系统调用跟踪的情况下运行它，并打印解码后的跟踪记录。
+
+/// name and number of arguments of the syscalls os5 knows about
+fn describe(id: usize) -> Option<(&'static str, usize)> {
+    Some(match id {
+        63 => ("read", 3),
+        64 => ("write", 3),
+        93 => ("exit", 1),
+        113 => ("clock_gettime", 2),
+        117 => ("ptrace", 4),
+        124 => ("sched_yield", 0),
+        140 => ("set_priority", 1),
+        153 => ("times", 1),
+        154 => ("setpgid", 2),
+        155 => ("getpgid", 1),
+        156 => ("getsid", 1),
+        157 => ("setsid", 0),
+        163 => ("getrlimit", 2),
+        164 => ("setrlimit", 2),
+        165 => ("getrusage", 2),
+        167 => ("prctl", 2),
+        169 => ("gettimeofday", 2),
+        172 => ("getpid", 0),
+        173 => ("getppid", 0),
+        215 => ("munmap", 2),
+        220 => ("fork", 0),
+        221 => ("exec", 1),
+        222 => ("mmap", 6),
+        233 => ("madvise", 3),
+        260 => ("waitpid", 2),
+        400 => ("spawn", 1),
+        410 => ("task_info", 1),
+        411 => ("task_maps", 3),
+        412 => ("meminfo", 2),
+        413 => ("trace", 2),
+        414 => ("trace_read", 3),
+        415 => ("seccomp", 1),
+        416 => ("proc_list", 2),
+        417 => ("proc_info", 2),
+        418 => ("coredump", 4),
+        425 => ("ring_setup", 1),
+        426 => ("ring_enter", 1),
+        _ => return None,
+    })
+}
+
+fn print_entry(entry: &TraceEntry) {
+    let mut call = String::new();
+    let nargs = match describe(entry.id) {
+        Some((name, nargs)) => {
+            call.push_str(name);
+            nargs
+        }
+        None => {
+            call.push_str(alloc::format!("syscall_{}", entry.id).as_str());
+            6
+        }
+    };
+    call.push('(');
+    for (i, arg) in entry.args.iter().take(nargs).enumerate() {
+        if i > 0 {
+            call.push_str(", ");
+        }
+        call.push_str(alloc::format!("{:#x}", arg).as_str());
+    }
+    call.push(')');
+    print!("[{:>10}us] {} = ", entry.start_us, call);
+    if entry.finished == 0 {
+        println!("?");
+    } else if entry.ret < 0 {
+        println!(
+            "{} {:?} <{}us>",
+            entry.ret,
+            Errno::from_code(entry.ret.unsigned_abs()),
+            entry.duration_us
+        );
+    } else {
+        println!("{} <{}us>", entry.ret, entry.duration_us);
+    }
+}
+
+fn read_line() -> String {
+    let mut line = String::new();
+    loop {
+        let c = getchar();
+        match c {
+            LF | CR => {
+                println!("");
+                return line;
+            }
+            _ => {
+                print!("{}", c as char);
+                flush();
+                line.push(c as char);
+            }
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    print!("strace: program to trace: ");
+    flush();
+    let name = read_line();
+    let mut path = name.clone();
+    path.push('\0');
+    // the child inherits tracing from us
+    assert_eq!(0, trace(-1, true));
+    let pid = spawn(path.as_str());
+    assert_eq!(0, trace(-1, false));
+    if pid < 0 {
+        println!("strace: can't run {}", name);
+        return -1;
+    }
+    let mut entries = [TraceEntry::default(); 16];
+    loop {
+        match sys::trace_read(pid, &mut entries) {
+            Ok(0) => {
+                yield_();
+            }
+            Ok(n) => entries[..n].iter().for_each(print_entry),
+            // exited and fully read
+            Err(Errno::ESRCH) => break,
+            Err(errno) => {
+                println!("strace: {:?}", errno);
+                break;
+            }
+        }
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(pid, waitpid(pid as usize, &mut exit_code));
+    println!("+++ exited with {} +++", exit_code);
+    0
+}
@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, sleep, sys, ProcInfo};
+
+/// 每秒刷新一次进程列表，按上一周期内的 CPU 占用排序，类似 top。
+
+const MAX_PROCS: usize = 64;
+const REFRESHES: usize = 10;
+const PERIOD_MS: usize = 1000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut prev = [ProcInfo::default(); MAX_PROCS];
+    let mut prev_len = 0;
+    let mut prev_time = get_time();
+    for _ in 0..REFRESHES {
+        let mut procs = [ProcInfo::default(); MAX_PROCS];
+        let len = sys::proc_list(&mut procs).unwrap().min(MAX_PROCS);
+        let now = get_time();
+        let elapsed_ns = ((now - prev_time).max(1) as usize) * 1_000_000;
+        // (permille of one hart, index into procs)
+        let mut usage = [(0usize, 0usize); MAX_PROCS];
+        for (i, p) in procs[..len].iter().enumerate() {
+            let before = prev[..prev_len]
+                .iter()
+                .find(|q| q.pid == p.pid)
+                .map_or(0, |q| q.cpu_time_ns);
+            usage[i] = (p.cpu_time_ns.saturating_sub(before) * 1000 / elapsed_ns, i);
+        }
+        usage[..len].sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+        print!("\x1b[2J\x1b[H");
+        println!("top - {} processes, uptime {} ms", len, now);
+        println!(
+            "{:>5} {:>5} {:<8} {:>5} {:>6} {:>10} {}",
+            "PID", "PPID", "STAT", "PRIO", "%CPU", "TIME(ms)", "NAME"
+        );
+        for &(permille, i) in &usage[..len] {
+            let p = &procs[i];
+            println!(
+                "{:>5} {:>5} {:<8} {:>5} {:>4}.{} {:>10} {}",
+                p.pid,
+                p.ppid,
+                p.status_name(),
+                p.priority,
+                permille / 10,
+                permille % 10,
+                p.cpu_time_ns / 1_000_000,
+                p.name()
+            );
+        }
+        prev = procs;
+        prev_len = len;
+        prev_time = now;
+        sleep(PERIOD_MS);
+    }
+    0
+}
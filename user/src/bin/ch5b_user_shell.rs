@@ -12,13 +12,120 @@ const DL: u8 = 0x7fu8;
 const BS: u8 = 0x08u8;
 
 use alloc::string::String;
+use alloc::vec::Vec;
 use user_lib::console::getchar;
-use user_lib::{exec, flush, fork, waitpid};
+use user_lib::{exec, exit, flush, fork, setpgid, sys, ExitInfo};
+
+/// a command left running in the background with `&`
+struct Job {
+    id: usize,
+    pid: usize,
+    command: String,
+}
+
+/// Fork and exec `command` (nul-terminated) in a process group of its own,
+/// return the pid of the child.
+fn launch(command: &str) -> Option<usize> {
+    let pid = fork();
+    if pid == 0 {
+        // child process
+        setpgid(0, 0);
+        if exec(command, &[0 as *const u8]) == -1 {
+            println!("Error when executing!");
+            exit(-4);
+        }
+        unreachable!();
+    }
+    if pid < 0 {
+        println!("Shell: fork failed");
+        return None;
+    }
+    // also set by the child, whichever runs first
+    setpgid(pid as usize, pid as usize);
+    Some(pid as usize)
+}
+
+fn wait_foreground(pid: usize) {
+    let mut info = ExitInfo::default();
+    let exit_pid = sys::waitpid_info(-(pid as isize), &mut info).unwrap();
+    assert_eq!(pid, exit_pid);
+    println!("Shell: Process {} {}", pid, info);
+}
+
+/// Reap the background jobs which have finished and report them.
+fn reap_jobs(jobs: &mut Vec<Job>) {
+    jobs.retain(|job| {
+        let mut info = ExitInfo::default();
+        match sys::try_waitpid_info(-(job.pid as isize), &mut info) {
+            Ok(None) => true,
+            Ok(Some(_)) => {
+                println!("[{}] Done, {}: {}", job.id, info, job.command);
+                false
+            }
+            Err(_) => false,
+        }
+    });
+}
+
+/// Run a line of input, which may be a builtin or end with `&`.
+fn run(line: &str, jobs: &mut Vec<Job>) {
+    let line = line.trim();
+    if line == "jobs" {
+        for job in jobs.iter() {
+            println!("[{}] Running {} {}", job.id, job.pid, job.command);
+        }
+        return;
+    }
+    if line == "fg" || line.starts_with("fg ") {
+        let arg = line[2..].trim().trim_start_matches('%');
+        let index = if arg.is_empty() {
+            jobs.len().checked_sub(1)
+        } else {
+            arg.parse::<usize>()
+                .ok()
+                .and_then(|id| jobs.iter().position(|job| job.id == id))
+        };
+        match index {
+            Some(index) => {
+                let job = jobs.remove(index);
+                println!("{}", job.command);
+                wait_foreground(job.pid);
+            }
+            None => println!("Shell: fg: no such job"),
+        }
+        return;
+    }
+    let (command, background) = match line.strip_suffix('&') {
+        Some(command) => (command.trim_end(), true),
+        None => (line, false),
+    };
+    if command.is_empty() {
+        return;
+    }
+    let mut path = String::from(command);
+    path.push('\0');
+    let pid = match launch(path.as_str()) {
+        Some(pid) => pid,
+        None => return,
+    };
+    if background {
+        let id = jobs.last().map_or(1, |job| job.id + 1);
+        println!("[{}] {}", id, pid);
+        jobs.push(Job {
+            id,
+            pid,
+            command: String::from(command),
+        });
+    } else {
+        wait_foreground(pid);
+    }
+}
 
 #[no_mangle]
 pub fn main() -> i32 {
     println!("Rust user shell");
     let mut line: String = String::new();
+    let mut jobs: Vec<Job> = Vec::new();
     print!(">> ");
     flush();
     loop {
@@ -27,23 +134,10 @@ pub fn main() -> i32 {
             LF | CR => {
                 print!("\n");
                 if !line.is_empty() {
-                    line.push('\0');
-                    let pid = fork();
-                    if pid == 0 {
-                        // child process
-                        if exec(line.as_str(), &[0 as *const u8]) == -1 {
-                            println!("Error when executing!");
-                            return -4;
-                        }
-                        unreachable!();
-                    } else {
-                        let mut exit_code: i32 = 0;
-                        let exit_pid = waitpid(pid as usize, &mut exit_code);
-                        assert_eq!(pid, exit_pid);
-                        println!("Shell: Process {} exited with code {}", pid, exit_code);
-                    }
+                    run(line.as_str(), &mut jobs);
                     line.clear();
                 }
+                reap_jobs(&mut jobs);
                 print!(">> ");
                 flush();
             }
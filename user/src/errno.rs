@@ -0,0 +1,76 @@
+//! Error numbers reported by the kernel
+//!
+//! A failed syscall returns the negated error number, see [`Errno::check`].
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Errno {
+    /// operation not permitted
+    EPERM,
+    /// no such file or directory
+    ENOENT,
+    /// no such process
+    ESRCH,
+    /// bad file descriptor
+    EBADF,
+    /// no child processes
+    ECHILD,
+    /// try again
+    EAGAIN,
+    /// out of memory
+    ENOMEM,
+    /// bad address
+    EFAULT,
+    /// already exists
+    EEXIST,
+    /// invalid argument
+    EINVAL,
+    /// invalid syscall number
+    ENOSYS,
+    /// a number this crate does not know about
+    Other(usize),
+}
+
+pub type Result<T> = core::result::Result<T, Errno>;
+
+impl Errno {
+    pub fn from_code(code: usize) -> Self {
+        match code {
+            1 => Errno::EPERM,
+            2 => Errno::ENOENT,
+            3 => Errno::ESRCH,
+            9 => Errno::EBADF,
+            10 => Errno::ECHILD,
+            11 => Errno::EAGAIN,
+            12 => Errno::ENOMEM,
+            14 => Errno::EFAULT,
+            17 => Errno::EEXIST,
+            22 => Errno::EINVAL,
+            38 => Errno::ENOSYS,
+            code => Errno::Other(code),
+        }
+    }
+    pub fn code(self) -> usize {
+        match self {
+            Errno::EPERM => 1,
+            Errno::ENOENT => 2,
+            Errno::ESRCH => 3,
+            Errno::EBADF => 9,
+            Errno::ECHILD => 10,
+            Errno::EAGAIN => 11,
+            Errno::ENOMEM => 12,
+            Errno::EFAULT => 14,
+            Errno::EEXIST => 17,
+            Errno::EINVAL => 22,
+            Errno::ENOSYS => 38,
+            Errno::Other(code) => code,
+        }
+    }
+    /// Decode the return value of a raw syscall.
+    pub fn check(ret: isize) -> Result<usize> {
+        if ret < 0 {
+            Err(Self::from_code(ret.unsigned_abs()))
+        } else {
+            Ok(ret as usize)
+        }
+    }
+}
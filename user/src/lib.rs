@@ -5,8 +5,15 @@
 
 #[macro_use]
 pub mod console;
+pub mod errno;
 mod lang_items;
+pub mod ptrace;
+pub mod ring;
+pub mod seccomp;
+pub mod sys;
 mod syscall;
+pub mod vdso;
+pub mod wait;
 
 extern crate alloc;
 extern crate core;
@@ -16,10 +23,20 @@ extern crate bitflags;
 use alloc::vec::Vec;
 use buddy_system_allocator::LockedHeap;
 pub use console::{flush, STDIN, STDOUT};
+pub use errno::Errno;
+pub use ring::Ring;
+pub use seccomp::{ArgOp, SeccompFilter};
 pub use syscall::*;
+pub use wait::ExitInfo;
 
 const USER_HEAP_SIZE: usize = 16384;
 
+/// Asks kernels with error numbers for them, instead of the -1 of the labs.
+/// The others do not look for it.
+#[used]
+#[link_section = ".rcore_errno"]
+static ERRNO_ABI: u8 = 1;
+
 static mut HEAP_SPACE: [u8; USER_HEAP_SIZE] = [0; USER_HEAP_SIZE];
 
 #[global_allocator]
@@ -46,8 +63,9 @@ fn clear_bss() {
 
 #[no_mangle]
 #[link_section = ".text.entry"]
-pub extern "C" fn _start(argc: usize, argv: usize) -> ! {
+pub extern "C" fn _start(argc: usize, argv: usize, vdso: usize) -> ! {
     clear_bss();
+    vdso::init(vdso);
     unsafe {
         HEAP.lock()
             .init(HEAP_SPACE.as_ptr() as usize, USER_HEAP_SIZE);
@@ -98,6 +116,103 @@ impl TimeVal {
     }
 }
 
+/// Reading of a clock, see [`clock_gettime`].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+impl TimeSpec {
+    pub fn as_ns(&self) -> usize {
+        self.sec * 1_000_000_000 + self.nsec
+    }
+}
+
+/// wall-clock time
+pub const CLOCK_REALTIME: usize = 0;
+/// time since boot
+pub const CLOCK_MONOTONIC: usize = 1;
+/// CPU time used by the calling process
+pub const CLOCK_PROCESS_CPUTIME_ID: usize = 2;
+
+impl TimeVal {
+    pub fn as_us(&self) -> usize {
+        self.sec * 1_000_000 + self.usec
+    }
+}
+
+/// Resources used by a process or its children, see [`getrusage`].
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct RUsage {
+    pub utime: TimeVal,
+    pub stime: TimeVal,
+    pub page_faults: usize,
+    pub voluntary_switches: usize,
+    pub involuntary_switches: usize,
+}
+
+/// CPU times in clock ticks, see [`times`].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Tms {
+    pub utime: usize,
+    pub stime: usize,
+    pub cutime: usize,
+    pub cstime: usize,
+}
+
+/// bytes in a process name, including the nul
+pub const COMM_LEN: usize = 16;
+pub const PR_SET_NAME: usize = 15;
+pub const PR_GET_NAME: usize = 16;
+
+/// The part of a process name before the nul.
+pub fn comm_str(name: &[u8; COMM_LEN]) -> &str {
+    let len = name.iter().position(|&ch| ch == 0).unwrap_or(COMM_LEN);
+    core::str::from_utf8(&name[..len]).unwrap_or("?")
+}
+
+/// One process as listed by [`proc_list`].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ProcInfo {
+    pub pid: usize,
+    /// -1 for the initial process
+    pub ppid: isize,
+    /// a [`TaskStatus`] as a number, 3 for a zombie
+    pub status: usize,
+    pub priority: usize,
+    pub stride: usize,
+    pub cpu_time_ns: usize,
+    pub pgid: usize,
+    pub sid: usize,
+    pub name: [u8; COMM_LEN],
+}
+
+impl ProcInfo {
+    pub fn name(&self) -> &str {
+        comm_str(&self.name)
+    }
+    pub fn status_name(&self) -> &'static str {
+        match self.status {
+            0 => "uninit",
+            1 => "ready",
+            2 => "running",
+            3 => "zombie",
+            4 => "stopped",
+            _ => "?",
+        }
+    }
+}
+
+/// clock ticks per second, the unit of [`Tms`]
+pub const CLK_TCK: usize = 100;
+pub const RUSAGE_SELF: isize = 0;
+pub const RUSAGE_CHILDREN: isize = -1;
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum TaskStatus {
     UnInit,
@@ -131,6 +246,66 @@ impl TaskInfo {
     }
 }
 
+/// Layout of one area of a process's memory set, see [`task_maps`].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MapAreaInfo {
+    pub start_vpn: usize,
+    pub end_vpn: usize,
+    /// 0 for identical, 1 for framed
+    pub map_type: usize,
+    /// `R W X U` at bits 1..=4, same as in a page table entry
+    pub map_perm: usize,
+    /// number of physical frames backing the area
+    pub resident: usize,
+    /// 1 if the area may be mapped with 2 MiB megapages
+    pub huge: usize,
+}
+
+/// Physical memory and kernel heap usage, see [`meminfo`].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MemInfo {
+    pub total_frames: usize,
+    pub free_frames: usize,
+    pub heap_total: usize,
+    pub heap_used: usize,
+    /// resident pages of the queried process
+    pub rss: usize,
+}
+
+/// Soft and hard limit of a resource, see [`getrlimit`].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RLimit {
+    pub cur: usize,
+    pub max: usize,
+}
+
+/// One syscall logged by a traced process, see [`trace_read`].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TraceEntry {
+    pub id: usize,
+    pub args: [usize; 6],
+    /// meaningful only once `finished` is 1
+    pub ret: isize,
+    /// microseconds since boot
+    pub start_us: usize,
+    pub duration_us: usize,
+    /// 0 if the syscall never returned, e.g. `exit`
+    pub finished: usize,
+}
+
+pub const RLIMIT_CPU: usize = 0;
+pub const RLIMIT_STACK: usize = 3;
+/// the largest core dump written, in bytes
+pub const RLIMIT_CORE: usize = 4;
+pub const RLIMIT_RSS: usize = 5;
+pub const RLIMIT_NPROC: usize = 6;
+pub const RLIMIT_AS: usize = 9;
+pub const RLIM_INFINITY: usize = usize::MAX;
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct Stat {
@@ -187,12 +362,22 @@ pub fn close(fd: usize) -> isize {
     sys_close(fd)
 }
 
+/// The helpers below report any error as -1, as kernels did before error
+/// numbers. Use [`sys`] to find out what went wrong.
+fn legacy(ret: isize) -> isize {
+    if ret < 0 {
+        -1
+    } else {
+        ret
+    }
+}
+
 pub fn read(fd: usize, buf: &mut [u8]) -> isize {
-    sys_read(fd, buf)
+    legacy(sys_read(fd, buf))
 }
 
 pub fn write(fd: usize, buf: &[u8]) -> isize {
-    sys_write(fd, buf)
+    legacy(sys_write(fd, buf))
 }
 
 pub fn link(old_path: &str, new_path: &str) -> isize {
@@ -224,52 +409,128 @@ pub fn yield_() -> isize {
     sys_yield()
 }
 
+fn time_ms(time: &TimeVal) -> isize {
+    ((time.sec & 0xffff) * 1000 + time.usec / 1000) as isize
+}
+
+/// Milliseconds since boot, read from the time page if the kernel maps one.
 pub fn get_time() -> isize {
+    match vdso::get_time() {
+        Some(time) => time_ms(&time),
+        None => get_time_trap(),
+    }
+}
+
+/// Like [`get_time`], but always asks the kernel.
+pub fn get_time_trap() -> isize {
     let time = TimeVal::new();
     match sys_get_time(&time, 0) {
-        0 => ((time.sec & 0xffff) * 1000 + time.usec / 1000) as isize,
+        0 => time_ms(&time),
         _ => -1,
     }
 }
 
+/// Resources used by the caller (`RUSAGE_SELF`) or by the children it has
+/// waited for (`RUSAGE_CHILDREN`), including their own waited-for children.
+pub fn getrusage(who: isize, usage: &mut RUsage) -> isize {
+    legacy(sys_getrusage(who, usage))
+}
+
+/// Fill `buf` with the CPU times of the caller and its waited-for children,
+/// return the clock ticks since boot.
+pub fn times(buf: &mut Tms) -> isize {
+    legacy(sys_times(buf))
+}
+
+/// Fill `buf` with the processes not reaped yet, in order of pid, return how
+/// many there are, which may be more than fit.
+pub fn proc_list(buf: &mut [ProcInfo]) -> isize {
+    legacy(sys_proc_list(buf))
+}
+
+/// Describe any process by pid.
+pub fn proc_info(pid: usize, info: &mut ProcInfo) -> isize {
+    legacy(sys_proc_info(pid, info))
+}
+
+/// Rename the calling process to `name`, which ends with a nul like the
+/// path of [`exec`]. Names longer than `COMM_LEN - 1` bytes are cut.
+pub fn set_name(name: &str) -> isize {
+    legacy(sys_prctl(PR_SET_NAME, name.as_ptr() as usize))
+}
+
+pub fn get_name(name: &mut [u8; COMM_LEN]) -> isize {
+    legacy(sys_prctl(PR_GET_NAME, name.as_mut_ptr() as usize))
+}
+
+/// Read the clock `clock_id`, from the time page if possible.
+pub fn clock_gettime(clock_id: usize, tp: &mut TimeSpec) -> isize {
+    match vdso::clock_gettime(clock_id) {
+        Some(time) => {
+            *tp = time;
+            0
+        }
+        None => legacy(sys_clock_gettime(clock_id, tp)),
+    }
+}
+
 pub fn getpid() -> isize {
     sys_getpid()
 }
 
+pub fn getppid() -> isize {
+    sys_getppid()
+}
+
+/// Move process `pid` (0 for the caller) into group `pgid` (0 for a new
+/// group named after `pid`).
+pub fn setpgid(pid: usize, pgid: usize) -> isize {
+    legacy(sys_setpgid(pid, pgid))
+}
+
+pub fn getpgid(pid: usize) -> isize {
+    legacy(sys_getpgid(pid))
+}
+
+pub fn getsid(pid: usize) -> isize {
+    legacy(sys_getsid(pid))
+}
+
+pub fn setsid() -> isize {
+    legacy(sys_setsid())
+}
+
 pub fn fork() -> isize {
-    sys_fork()
+    legacy(sys_fork())
 }
 
 pub fn exec(path: &str, args: &[*const u8]) -> isize {
-    sys_exec(path, args)
+    legacy(sys_exec(path, args))
 }
 
 pub fn set_priority(prio: isize) -> isize {
-    sys_set_priority(prio)
+    legacy(sys_set_priority(prio))
 }
 
 pub fn wait(exit_code: &mut i32) -> isize {
-    loop {
-        match sys_waitpid(-1, exit_code as *mut _) {
-            -2 => {
-                sys_yield();
-            }
-            n => {
-                return n;
-            }
-        }
-    }
+    waitpid(usize::MAX, exit_code)
 }
 
+/// `pid` of `usize::MAX` waits for any child.
 pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
     loop {
         match sys_waitpid(pid as isize, exit_code as *mut _) {
+            // -2 is "not yet" from kernels without error numbers; os5
+            // never fails waitpid with ENOENT, which is -2 as well
             -2 => {
                 sys_yield();
             }
-            n => {
-                return n;
-            }
+            n => match Errno::check(n) {
+                Err(Errno::EAGAIN) => {
+                    sys_yield();
+                }
+                _ => return legacy(n),
+            },
         }
     }
 }
@@ -284,16 +545,32 @@ pub fn sleep(period_ms: usize) {
         sys_yield();
     }
 }
+pub const MAP_PRIVATE: usize = 0x02;
+pub const MAP_FIXED: usize = 0x10;
+pub const MAP_ANONYMOUS: usize = 0x20;
+/// back a 2 MiB aligned region with megapages
+pub const MAP_HUGETLB: usize = 0x40000;
+/// make the pages fault as if their memory were broken, see [`sys::madvise`]
+pub const MADV_HWPOISON: usize = 100;
+
+/// Map anonymous memory at `start`, see [`sys::mmap`] for the flags.
 pub fn mmap(start: usize, len: usize, prot: usize) -> isize {
-    sys_mmap(start, len, prot)
+    legacy(sys_mmap(
+        start,
+        len,
+        prot,
+        MAP_PRIVATE | MAP_FIXED | MAP_ANONYMOUS,
+        -1,
+        0,
+    ))
 }
 
 pub fn munmap(start: usize, len: usize) -> isize {
-    sys_munmap(start, len)
+    legacy(sys_munmap(start, len))
 }
 
 pub fn spawn(path: &str) -> isize {
-    sys_spawn(path)
+    legacy(sys_spawn(path))
 }
 
 pub fn dup(fd: usize) -> isize {
@@ -304,7 +581,74 @@ pub fn pipe(pipe_fd: &mut [usize]) -> isize {
 }
 
 pub fn task_info(info: &TaskInfo) -> isize {
-    sys_task_info(info)
+    legacy(sys_task_info(info))
+}
+
+pub fn task_maps(pid: usize, buf: &mut [MapAreaInfo]) -> isize {
+    legacy(sys_task_maps(pid, buf))
+}
+
+const MAX_MAP_AREAS: usize = 32;
+const PAGE_SIZE: usize = 4096;
+
+/// Print the memory areas of process `pid` in the style of `/proc/<pid>/maps`.
+pub fn print_maps(pid: usize) -> isize {
+    let mut areas = [MapAreaInfo::default(); MAX_MAP_AREAS];
+    let n = task_maps(pid, &mut areas);
+    if n < 0 {
+        return n;
+    }
+    println!("{:>16}-{:<16} perm type      pages resident huge", "start", "end");
+    for area in areas.iter().take((n as usize).min(MAX_MAP_AREAS)) {
+        let perm = |bit: usize, c: char| if area.map_perm & (1 << bit) != 0 { c } else { '-' };
+        println!(
+            "{:016x}-{:016x} {}{}{}{} {:<9} {:>5} {:>8} {}",
+            area.start_vpn * PAGE_SIZE,
+            area.end_vpn * PAGE_SIZE,
+            perm(1, 'r'),
+            perm(2, 'w'),
+            perm(3, 'x'),
+            perm(4, 'u'),
+            match area.map_type {
+                0 => "identical",
+                1 => "framed",
+                _ => "unknown",
+            },
+            area.end_vpn - area.start_vpn,
+            area.resident,
+            if area.huge != 0 { "yes" } else { "no" },
+        );
+    }
+    if n as usize > MAX_MAP_AREAS {
+        println!("... {} more areas", n as usize - MAX_MAP_AREAS);
+    }
+    n
+}
+
+/// Query memory usage; `pid` selects whose resident pages are reported,
+/// -1 meaning the caller.
+pub fn meminfo(pid: isize, info: &mut MemInfo) -> isize {
+    legacy(sys_meminfo(pid, info))
+}
+
+pub fn getrlimit(resource: usize, rlim: &mut RLimit) -> isize {
+    legacy(sys_getrlimit(resource, rlim))
+}
+/// The hard limit can only be lowered; the limits are inherited by
+/// children created afterwards.
+pub fn setrlimit(resource: usize, rlim: &RLimit) -> isize {
+    legacy(sys_setrlimit(resource, rlim))
+}
+
+/// Trace the syscalls of the caller (`pid` -1) or one of its children.
+/// Children created afterwards inherit the setting.
+pub fn trace(pid: isize, enable: bool) -> isize {
+    legacy(sys_trace(pid, enable))
+}
+/// Move the oldest logged syscalls of the caller (`pid` -1) or one of its
+/// children into `buf`, return how many were moved.
+pub fn trace_read(pid: isize, buf: &mut [TraceEntry]) -> isize {
+    legacy(sys_trace_read(pid, buf))
 }
 
 pub fn thread_create(entry: usize, arg: usize) -> isize {
@@ -0,0 +1,140 @@
+//! Debugging a child with [`ptrace`](crate::syscall::sys_ptrace), os5 only.
+//!
+//! ```ignore
+//! let pid = fork();
+//! if pid == 0 {
+//!     ptrace::traceme().unwrap();
+//!     exec("app\0", &[core::ptr::null()]);
+//! }
+//! // stopped on entering the new image
+//! sys::waitpid_info(pid as isize, &mut info).unwrap();
+//! let regs = ptrace::getregs(pid as usize).unwrap();
+//! ptrace::singlestep(pid as usize).unwrap();
+//! ```
+//!
+//! A traced child stops on `exec`, on an `ebreak` and after a single step,
+//! each reported once by `waitpid` with [`wifstopped`](crate::wait::wifstopped).
+
+use crate::errno::{Errno, Result};
+use crate::syscall::sys_ptrace;
+
+pub const PTRACE_TRACEME: usize = 0;
+pub const PTRACE_PEEKDATA: usize = 2;
+pub const PTRACE_POKEDATA: usize = 5;
+pub const PTRACE_CONT: usize = 7;
+pub const PTRACE_KILL: usize = 8;
+pub const PTRACE_SINGLESTEP: usize = 9;
+pub const PTRACE_GETREGS: usize = 12;
+pub const PTRACE_SETREGS: usize = 13;
+pub const PTRACE_ATTACH: usize = 16;
+pub const PTRACE_DETACH: usize = 17;
+
+/// `ebreak`, which stops a traced process
+pub const EBREAK: u32 = 0x0010_0073;
+/// `c.ebreak`
+pub const C_EBREAK: u16 = 0x9002;
+
+/// the registers of a stopped child
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct UserRegs {
+    pub pc: usize,
+    /// `x1` to `x31`
+    pub x: [usize; 31],
+}
+
+impl Default for UserRegs {
+    fn default() -> Self {
+        Self { pc: 0, x: [0; 31] }
+    }
+}
+
+impl UserRegs {
+    /// Register `xn`, zero for `x0`.
+    pub fn reg(&self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            self.x[n - 1]
+        }
+    }
+    pub fn set_reg(&mut self, n: usize, value: usize) {
+        if n != 0 {
+            self.x[n - 1] = value;
+        }
+    }
+}
+
+fn request(request: usize, pid: usize, addr: usize, data: usize) -> Result<()> {
+    Errno::check(sys_ptrace(request, pid, addr, data)).map(|_| ())
+}
+
+/// Let the parent trace the caller, which stops on its next `exec`.
+pub fn traceme() -> Result<()> {
+    request(PTRACE_TRACEME, 0, 0, 0)
+}
+
+/// Trace child `pid`, which stops soon after.
+pub fn attach(pid: usize) -> Result<()> {
+    request(PTRACE_ATTACH, pid, 0, 0)
+}
+
+pub fn detach(pid: usize) -> Result<()> {
+    request(PTRACE_DETACH, pid, 0, 0)
+}
+
+pub fn cont(pid: usize) -> Result<()> {
+    request(PTRACE_CONT, pid, 0, 0)
+}
+
+/// Run one instruction of child `pid`, which then stops with `SIGTRAP`.
+pub fn singlestep(pid: usize) -> Result<()> {
+    request(PTRACE_SINGLESTEP, pid, 0, 0)
+}
+
+pub fn kill(pid: usize) -> Result<()> {
+    request(PTRACE_KILL, pid, 0, 0)
+}
+
+/// Read the doubleword at `addr` of child `pid`.
+pub fn peek(pid: usize, addr: usize) -> Result<u64> {
+    let mut word = 0u64;
+    request(PTRACE_PEEKDATA, pid, addr, &mut word as *mut _ as usize)?;
+    Ok(word)
+}
+
+/// Write the doubleword at `addr` of child `pid`, even in its code.
+pub fn poke(pid: usize, addr: usize, word: u64) -> Result<()> {
+    request(PTRACE_POKEDATA, pid, addr, word as usize)
+}
+
+pub fn getregs(pid: usize) -> Result<UserRegs> {
+    let mut regs = UserRegs::default();
+    request(PTRACE_GETREGS, pid, 0, &mut regs as *mut _ as usize)?;
+    Ok(regs)
+}
+
+pub fn setregs(pid: usize, regs: &UserRegs) -> Result<()> {
+    request(PTRACE_SETREGS, pid, 0, regs as *const _ as usize)
+}
+
+/// A software breakpoint: `c.ebreak` in place of the halfword at `addr`.
+pub struct Breakpoint {
+    pub addr: usize,
+    saved: u64,
+}
+
+impl Breakpoint {
+    /// Plant a breakpoint at `addr` of stopped child `pid`.
+    pub fn insert(pid: usize, addr: usize) -> Result<Self> {
+        let saved = peek(pid, addr)?;
+        poke(pid, addr, saved & !0xffff | C_EBREAK as u64)?;
+        Ok(Self { addr, saved })
+    }
+    /// Put the original instruction back, e.g. to step over it once the
+    /// breakpoint is hit.
+    pub fn remove(&self, pid: usize) -> Result<()> {
+        let word = peek(pid, self.addr)?;
+        poke(pid, self.addr, word & !0xffff | self.saved & 0xffff)
+    }
+}
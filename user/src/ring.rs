@@ -0,0 +1,112 @@
+//! Batching syscalls through the ring shared with the kernel.
+//!
+//! ```ignore
+//! let mut ring = Ring::setup(16)?;
+//! ring.push(SYSCALL_GETPID, [0; 6], 1);
+//! ring.push(SYSCALL_YIELD, [0; 6], 2);
+//! ring.submit()?;
+//! while let Some(completion) = ring.pop() { ... }
+//! ```
+
+use crate::errno::{Errno, Result};
+use crate::syscall::{sys_ring_enter, sys_ring_setup};
+use core::mem::size_of;
+use core::ptr::{addr_of, addr_of_mut, read_volatile, write_volatile};
+
+pub const RING_MAX_ENTRIES: usize = 256;
+
+#[repr(C)]
+pub struct RingHeader {
+    pub entries: usize,
+    pub sq_head: usize,
+    pub sq_tail: usize,
+    pub cq_head: usize,
+    pub cq_tail: usize,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Submission {
+    pub id: usize,
+    pub args: [usize; 6],
+    pub user_data: usize,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Completion {
+    pub user_data: usize,
+    /// what the syscall would have returned, a negative errno on failure
+    pub result: isize,
+}
+
+/// The ring of the calling process. A child created by `fork` has a copy of
+/// its own at the same address.
+pub struct Ring {
+    base: usize,
+    entries: usize,
+}
+
+impl Ring {
+    /// Map the ring with `entries` slots, a power of two up to
+    /// [`RING_MAX_ENTRIES`]. A process has one ring at most.
+    pub fn setup(entries: usize) -> Result<Self> {
+        let base = Errno::check(sys_ring_setup(entries))?;
+        Ok(Self { base, entries })
+    }
+    /// where the ring is mapped, which `munmap` refuses to touch
+    pub fn base(&self) -> usize {
+        self.base
+    }
+    fn header(&self) -> *mut RingHeader {
+        self.base as *mut RingHeader
+    }
+    fn submission(&self, index: usize) -> *mut Submission {
+        let base = self.base + size_of::<RingHeader>();
+        unsafe { (base as *mut Submission).add(index % self.entries) }
+    }
+    fn completion(&self, index: usize) -> *mut Completion {
+        let base = self.base + size_of::<RingHeader>() + self.entries * size_of::<Submission>();
+        unsafe { (base as *mut Completion).add(index % self.entries) }
+    }
+    /// Queue syscall `id`, return `false` if the submission queue is full.
+    pub fn push(&mut self, id: usize, args: [usize; 6], user_data: usize) -> bool {
+        let header = self.header();
+        unsafe {
+            let head = read_volatile(addr_of!((*header).sq_head));
+            let tail = read_volatile(addr_of!((*header).sq_tail));
+            if tail.wrapping_sub(head) == self.entries {
+                return false;
+            }
+            write_volatile(self.submission(tail), Submission { id, args, user_data });
+            write_volatile(addr_of_mut!((*header).sq_tail), tail.wrapping_add(1));
+        }
+        true
+    }
+    /// submissions the kernel has not taken yet
+    pub fn pending(&self) -> usize {
+        let header = self.header();
+        unsafe {
+            let head = read_volatile(addr_of!((*header).sq_head));
+            read_volatile(addr_of!((*header).sq_tail)).wrapping_sub(head)
+        }
+    }
+    /// Have the kernel run the pending submissions with a single trap. Fewer
+    /// than [`Ring::pending`] are run if the completion queue fills up.
+    pub fn submit(&mut self) -> Result<usize> {
+        Errno::check(sys_ring_enter(self.pending()))
+    }
+    /// Take the oldest completion.
+    pub fn pop(&mut self) -> Option<Completion> {
+        let header = self.header();
+        unsafe {
+            let head = read_volatile(addr_of!((*header).cq_head));
+            if head == read_volatile(addr_of!((*header).cq_tail)) {
+                return None;
+            }
+            let completion = read_volatile(self.completion(head));
+            write_volatile(addr_of_mut!((*header).cq_head), head.wrapping_add(1));
+            Some(completion)
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Building syscall filters for [`seccomp`](crate::seccomp).
+//!
+//! ```ignore
+//! SeccompFilter::new()
+//!     .allow(SYSCALL_WRITE)
+//!     // no executable mappings
+//!     .allow_if(SYSCALL_MMAP, 2, ArgOp::MaskedZero, 0b100)
+//!     .kill()
+//!     .install();
+//! ```
+
+use crate::errno::{Errno, Result};
+use crate::syscall::sys_seccomp;
+use alloc::vec::Vec;
+
+const MAX_SYSCALL_NUM: usize = 500;
+const FILTER_WORDS: usize = (MAX_SYSCALL_NUM + 63) / 64;
+/// predicates the kernel accepts, counting those of earlier filters
+pub const MAX_ARG_PREDICATES: usize = 32;
+/// exit code of a process killed by its filter
+pub const SECCOMP_KILLED_EXIT_CODE: i32 = -31;
+
+/// how an argument is compared with the value of a predicate, unsigned
+#[derive(Copy, Clone, Debug)]
+pub enum ArgOp {
+    Eq = 0,
+    Ne = 1,
+    Lt = 2,
+    Le = 3,
+    Gt = 4,
+    Ge = 5,
+    /// none of the bits in the value are set
+    MaskedZero = 6,
+}
+
+/// `args[arg] op value` must hold for syscall `id`
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct SeccompArg {
+    pub id: usize,
+    pub arg: usize,
+    pub op: usize,
+    pub value: usize,
+}
+
+/// what the kernel reads from the pointer passed to `sys_seccomp`
+#[repr(C)]
+pub struct SeccompProg {
+    pub allowed: [u64; FILTER_WORDS],
+    /// 0 to fail rejected syscalls with `EPERM`, 1 to kill the process
+    pub action: usize,
+    pub npredicates: usize,
+    pub predicates: *const SeccompArg,
+}
+
+/// A filter allowing nothing but `exit` until told otherwise. Rejected
+/// syscalls fail with `EPERM` unless [`SeccompFilter::kill`] is called.
+pub struct SeccompFilter {
+    allowed: [u64; FILTER_WORDS],
+    predicates: Vec<SeccompArg>,
+    kill: bool,
+}
+
+impl SeccompFilter {
+    pub fn new() -> Self {
+        Self {
+            allowed: [0; FILTER_WORDS],
+            predicates: Vec::new(),
+            kill: false,
+        }
+    }
+    pub fn allow(mut self, id: usize) -> Self {
+        self.allowed[id / 64] |= 1 << (id % 64);
+        self
+    }
+    /// Allow `id` as long as `args[arg] op value` holds, along with any other
+    /// predicate given for it.
+    pub fn allow_if(mut self, id: usize, arg: usize, op: ArgOp, value: usize) -> Self {
+        self.predicates.push(SeccompArg {
+            id,
+            arg,
+            op: op as usize,
+            value,
+        });
+        self.allow(id)
+    }
+    /// Kill the process instead of failing a rejected syscall.
+    pub fn kill(mut self) -> Self {
+        self.kill = true;
+        self
+    }
+    /// Restrict the caller and the children it creates afterwards. Filters
+    /// installed before stay in force.
+    pub fn install(&self) -> Result<()> {
+        let prog = SeccompProg {
+            allowed: self.allowed,
+            action: self.kill as usize,
+            npredicates: self.predicates.len(),
+            predicates: self.predicates.as_ptr(),
+        };
+        Errno::check(sys_seccomp(&prog)).map(|_| ())
+    }
+}
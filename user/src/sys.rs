@@ -0,0 +1,227 @@
+//! `Result`-returning counterparts of the syscall helpers in the crate root,
+//! which predate [`Errno`] and report any error as -1.
+
+use crate::errno::{Errno, Result};
+use crate::syscall::*;
+use crate::{
+    MapAreaInfo, MemInfo, ProcInfo, RLimit, RUsage, TaskInfo, TimeSpec, TimeVal, Tms, TraceEntry,
+};
+use crate::{ExitInfo, COMM_LEN, PR_GET_NAME, PR_SET_NAME};
+use crate::{MAP_ANONYMOUS, MAP_FIXED, MAP_PRIVATE};
+
+pub fn read(fd: usize, buf: &mut [u8]) -> Result<usize> {
+    Errno::check(sys_read(fd, buf))
+}
+
+pub fn write(fd: usize, buf: &[u8]) -> Result<usize> {
+    Errno::check(sys_write(fd, buf))
+}
+
+/// Read the time page if the kernel maps one, trap otherwise.
+pub fn get_time() -> Result<TimeVal> {
+    if let Some(time) = crate::vdso::get_time() {
+        return Ok(time);
+    }
+    let time = TimeVal::new();
+    Errno::check(sys_get_time(&time, 0))?;
+    Ok(time)
+}
+
+/// Read the time page if the kernel maps one and it covers `clock_id`,
+/// trap otherwise.
+pub fn clock_gettime(clock_id: usize) -> Result<TimeSpec> {
+    if let Some(time) = crate::vdso::clock_gettime(clock_id) {
+        return Ok(time);
+    }
+    let mut time = TimeSpec::default();
+    Errno::check(sys_clock_gettime(clock_id, &mut time))?;
+    Ok(time)
+}
+
+pub fn getrusage(who: isize) -> Result<RUsage> {
+    let mut usage = RUsage::default();
+    Errno::check(sys_getrusage(who, &mut usage))?;
+    Ok(usage)
+}
+
+/// CPU times and the clock ticks since boot
+pub fn times() -> Result<(Tms, usize)> {
+    let mut tms = Tms::default();
+    let ticks = Errno::check(sys_times(&mut tms))?;
+    Ok((tms, ticks))
+}
+
+pub fn fork() -> Result<usize> {
+    Errno::check(sys_fork())
+}
+
+pub fn getppid() -> usize {
+    sys_getppid() as usize
+}
+
+/// Move process `pid` (0 for the caller) into group `pgid` (0 for a new
+/// group named after `pid`).
+pub fn setpgid(pid: usize, pgid: usize) -> Result<()> {
+    Errno::check(sys_setpgid(pid, pgid)).map(|_| ())
+}
+
+pub fn getpgid(pid: usize) -> Result<usize> {
+    Errno::check(sys_getpgid(pid))
+}
+
+pub fn getsid(pid: usize) -> Result<usize> {
+    Errno::check(sys_getsid(pid))
+}
+
+/// Start a new session led by the caller, return its id.
+pub fn setsid() -> Result<usize> {
+    Errno::check(sys_setsid())
+}
+
+/// Only returns if the new image can't be loaded.
+pub fn exec(path: &str, args: &[*const u8]) -> Result<()> {
+    Errno::check(sys_exec(path, args)).map(|_| ())
+}
+
+/// Reap child `pid` if it has exited, without waiting. `pid` is as for
+/// [`waitpid`].
+pub fn try_waitpid(pid: isize, exit_code: &mut i32) -> Result<Option<usize>> {
+    match Errno::check(sys_waitpid(pid, exit_code as *mut _)) {
+        Ok(pid) => Ok(Some(pid)),
+        Err(Errno::EAGAIN) => Ok(None),
+        Err(errno) => Err(errno),
+    }
+}
+
+/// Wait for child `pid` to exit. A `pid` of -1 means any child, 0 any child
+/// in the caller's process group, and below -1 any child in group `-pid`.
+pub fn waitpid(pid: isize, exit_code: &mut i32) -> Result<usize> {
+    loop {
+        match Errno::check(sys_waitpid(pid, exit_code as *mut _)) {
+            Err(Errno::EAGAIN) => {
+                sys_yield();
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Wait for child `pid` as [`waitpid`] does, and describe how it ended.
+pub fn waitpid_info(pid: isize, info: &mut ExitInfo) -> Result<usize> {
+    loop {
+        match try_waitpid_info(pid, info) {
+            Ok(None) => {
+                sys_yield();
+            }
+            Ok(Some(pid)) => return Ok(pid),
+            Err(errno) => return Err(errno),
+        }
+    }
+}
+
+/// Reap child `pid` if it has exited, without waiting, and describe how it
+/// ended.
+pub fn try_waitpid_info(pid: isize, info: &mut ExitInfo) -> Result<Option<usize>> {
+    match Errno::check(sys_waitpid_info(pid, core::ptr::null_mut(), info)) {
+        Ok(pid) => Ok(Some(pid)),
+        Err(Errno::EAGAIN) => Ok(None),
+        Err(errno) => Err(errno),
+    }
+}
+
+pub fn set_priority(prio: isize) -> Result<usize> {
+    Errno::check(sys_set_priority(prio))
+}
+
+pub fn mmap(start: usize, len: usize, prot: usize) -> Result<()> {
+    mmap_flags(start, len, prot, MAP_PRIVATE | MAP_FIXED | MAP_ANONYMOUS, -1, 0)
+}
+
+/// Only anonymous mappings are supported: `flags` must contain
+/// [`MAP_ANONYMOUS`], `fd` must be -1 and `offset` 0.
+pub fn mmap_flags(
+    start: usize,
+    len: usize,
+    prot: usize,
+    flags: usize,
+    fd: isize,
+    offset: usize,
+) -> Result<()> {
+    Errno::check(sys_mmap(start, len, prot, flags, fd, offset)).map(|_| ())
+}
+
+pub fn munmap(start: usize, len: usize) -> Result<()> {
+    Errno::check(sys_munmap(start, len)).map(|_| ())
+}
+
+/// Only [`crate::MADV_HWPOISON`] is supported: the contents of the pages
+/// are lost, and any access to them faults, in the process or in the kernel.
+pub fn madvise(start: usize, len: usize, advice: usize) -> Result<()> {
+    Errno::check(sys_madvise(start, len, advice)).map(|_| ())
+}
+
+pub fn spawn(path: &str) -> Result<usize> {
+    Errno::check(sys_spawn(path))
+}
+
+pub fn task_info(info: &mut TaskInfo) -> Result<()> {
+    Errno::check(sys_task_info(info)).map(|_| ())
+}
+
+/// Return the total number of areas, which may exceed `buf.len()`.
+pub fn task_maps(pid: usize, buf: &mut [MapAreaInfo]) -> Result<usize> {
+    Errno::check(sys_task_maps(pid, buf))
+}
+
+pub fn meminfo(pid: isize) -> Result<MemInfo> {
+    let mut info = MemInfo::default();
+    Errno::check(sys_meminfo(pid, &mut info))?;
+    Ok(info)
+}
+
+/// Return the total number of processes, of which the first ones fill `buf`.
+pub fn proc_list(buf: &mut [ProcInfo]) -> Result<usize> {
+    Errno::check(sys_proc_list(buf))
+}
+
+/// Read the core dump of child `pid` from `offset` on into `buf`, and return
+/// the size of the whole dump. The dump is there once the child is reaped,
+/// until another child which dumped core is.
+pub fn coredump(pid: usize, offset: usize, buf: &mut [u8]) -> Result<usize> {
+    Errno::check(sys_coredump(pid, offset, buf))
+}
+
+pub fn proc_info(pid: usize) -> Result<ProcInfo> {
+    let mut info = ProcInfo::default();
+    Errno::check(sys_proc_info(pid, &mut info))?;
+    Ok(info)
+}
+
+/// Rename the calling process, see [`crate::set_name`].
+pub fn set_name(name: &str) -> Result<()> {
+    Errno::check(sys_prctl(PR_SET_NAME, name.as_ptr() as usize)).map(|_| ())
+}
+
+pub fn get_name() -> Result<[u8; COMM_LEN]> {
+    let mut name = [0u8; COMM_LEN];
+    Errno::check(sys_prctl(PR_GET_NAME, name.as_mut_ptr() as usize))?;
+    Ok(name)
+}
+
+pub fn getrlimit(resource: usize) -> Result<RLimit> {
+    let mut rlim = RLimit::default();
+    Errno::check(sys_getrlimit(resource, &mut rlim))?;
+    Ok(rlim)
+}
+
+pub fn setrlimit(resource: usize, rlim: &RLimit) -> Result<()> {
+    Errno::check(sys_setrlimit(resource, rlim)).map(|_| ())
+}
+
+pub fn trace(pid: isize, enable: bool) -> Result<()> {
+    Errno::check(sys_trace(pid, enable)).map(|_| ())
+}
+
+pub fn trace_read(pid: isize, buf: &mut [TraceEntry]) -> Result<usize> {
+    Errno::check(sys_trace_read(pid, buf))
+}
@@ -1,6 +1,7 @@
-use crate::TaskInfo;
+use crate::seccomp::SeccompProg;
+use crate::{ExitInfo, MapAreaInfo, MemInfo, ProcInfo, RLimit, RUsage, TaskInfo, Tms, TraceEntry};
 
-use super::{Stat, TimeVal};
+use super::{Stat, TimeSpec, TimeVal};
 
 pub const SYSCALL_OPENAT: usize = 56;
 pub const SYSCALL_CLOSE: usize = 57;
@@ -11,22 +12,45 @@ pub const SYSCALL_LINKAT: usize = 37;
 pub const SYSCALL_FSTAT: usize = 80;
 pub const SYSCALL_EXIT: usize = 93;
 pub const SYSCALL_SLEEP: usize = 101;
+pub const SYSCALL_CLOCK_GETTIME: usize = 113;
+pub const SYSCALL_PTRACE: usize = 117;
 pub const SYSCALL_YIELD: usize = 124;
 pub const SYSCALL_GETTIMEOFDAY: usize = 169;
 pub const SYSCALL_GETPID: usize = 172;
+pub const SYSCALL_GETPPID: usize = 173;
 pub const SYSCALL_GETTID: usize = 178;
 pub const SYSCALL_FORK: usize = 220;
 pub const SYSCALL_EXEC: usize = 221;
 pub const SYSCALL_WAITPID: usize = 260;
 pub const SYSCALL_SET_PRIORITY: usize = 140;
+pub const SYSCALL_TIMES: usize = 153;
+pub const SYSCALL_SETPGID: usize = 154;
+pub const SYSCALL_GETPGID: usize = 155;
+pub const SYSCALL_GETSID: usize = 156;
+pub const SYSCALL_SETSID: usize = 157;
+pub const SYSCALL_GETRLIMIT: usize = 163;
+pub const SYSCALL_SETRLIMIT: usize = 164;
+pub const SYSCALL_GETRUSAGE: usize = 165;
+pub const SYSCALL_PRCTL: usize = 167;
 pub const SYSCALL_MUNMAP: usize = 215;
 pub const SYSCALL_MMAP: usize = 222;
+pub const SYSCALL_MADVISE: usize = 233;
 pub const SYSCALL_SPAWN: usize = 400;
 pub const SYSCALL_MAIL_READ: usize = 401;
 pub const SYSCALL_MAIL_WRITE: usize = 402;
 pub const SYSCALL_DUP: usize = 24;
 pub const SYSCALL_PIPE: usize = 59;
 pub const SYSCALL_TASK_INFO: usize = 410;
+pub const SYSCALL_TASK_MAPS: usize = 411;
+pub const SYSCALL_MEMINFO: usize = 412;
+pub const SYSCALL_TRACE: usize = 413;
+pub const SYSCALL_TRACE_READ: usize = 414;
+pub const SYSCALL_SECCOMP: usize = 415;
+pub const SYSCALL_PROC_LIST: usize = 416;
+pub const SYSCALL_PROC_INFO: usize = 417;
+pub const SYSCALL_COREDUMP: usize = 418;
+pub const SYSCALL_RING_SETUP: usize = 425;
+pub const SYSCALL_RING_ENTER: usize = 426;
 pub const SYSCALL_THREAD_CREATE: usize = 460;
 pub const SYSCALL_WAITTID: usize = 462;
 pub const SYSCALL_MUTEX_CREATE: usize = 463;
@@ -158,10 +182,46 @@ pub fn sys_get_time(time: &TimeVal, tz: usize) -> isize {
     syscall(SYSCALL_GETTIMEOFDAY, [time as *const _ as usize, tz, 0])
 }
 
+pub fn sys_clock_gettime(clock_id: usize, tp: &mut TimeSpec) -> isize {
+    syscall(SYSCALL_CLOCK_GETTIME, [clock_id, tp as *mut _ as usize, 0])
+}
+
+pub fn sys_getrusage(who: isize, usage: &mut RUsage) -> isize {
+    syscall(SYSCALL_GETRUSAGE, [who as usize, usage as *mut _ as usize, 0])
+}
+
+pub fn sys_prctl(option: usize, arg: usize) -> isize {
+    syscall(SYSCALL_PRCTL, [option, arg, 0])
+}
+
+pub fn sys_times(buf: &mut Tms) -> isize {
+    syscall(SYSCALL_TIMES, [buf as *mut _ as usize, 0, 0])
+}
+
 pub fn sys_getpid() -> isize {
     syscall(SYSCALL_GETPID, [0, 0, 0])
 }
 
+pub fn sys_getppid() -> isize {
+    syscall(SYSCALL_GETPPID, [0, 0, 0])
+}
+
+pub fn sys_setpgid(pid: usize, pgid: usize) -> isize {
+    syscall(SYSCALL_SETPGID, [pid, pgid, 0])
+}
+
+pub fn sys_getpgid(pid: usize) -> isize {
+    syscall(SYSCALL_GETPGID, [pid, 0, 0])
+}
+
+pub fn sys_getsid(pid: usize) -> isize {
+    syscall(SYSCALL_GETSID, [pid, 0, 0])
+}
+
+pub fn sys_setsid() -> isize {
+    syscall(SYSCALL_SETSID, [0, 0, 0])
+}
+
 pub fn sys_fork() -> isize {
     syscall(SYSCALL_FORK, [0, 0, 0])
 }
@@ -177,18 +237,37 @@ pub fn sys_waitpid(pid: isize, xstatus: *mut i32) -> isize {
     syscall(SYSCALL_WAITPID, [pid as usize, xstatus as usize, 0])
 }
 
+/// `waitpid` also filling an [`ExitInfo`], os5 only
+pub fn sys_waitpid_info(pid: isize, xstatus: *mut i32, info: *mut ExitInfo) -> isize {
+    syscall(
+        SYSCALL_WAITPID,
+        [pid as usize, xstatus as usize, info as usize],
+    )
+}
+
 pub fn sys_set_priority(prio: isize) -> isize {
     syscall(SYSCALL_SET_PRIORITY, [prio as usize, 0, 0])
 }
 
-pub fn sys_mmap(start: usize, len: usize, prot: usize) -> isize {
-    syscall(SYSCALL_MMAP, [start, len, prot])
+pub fn sys_mmap(
+    start: usize,
+    len: usize,
+    prot: usize,
+    flags: usize,
+    fd: isize,
+    offset: usize,
+) -> isize {
+    syscall6(SYSCALL_MMAP, [start, len, prot, flags, fd as usize, offset])
 }
 
 pub fn sys_munmap(start: usize, len: usize) -> isize {
     syscall(SYSCALL_MUNMAP, [start, len, 0])
 }
 
+pub fn sys_madvise(start: usize, len: usize, advice: usize) -> isize {
+    syscall(SYSCALL_MADVISE, [start, len, advice])
+}
+
 pub fn sys_spawn(path: &str) -> isize {
     syscall(SYSCALL_SPAWN, [path.as_ptr() as usize, 0, 0])
 }
@@ -205,6 +284,71 @@ pub fn sys_task_info(info: &TaskInfo) -> isize {
     syscall(SYSCALL_TASK_INFO, [info as *const _ as usize, 0, 0])
 }
 
+pub fn sys_task_maps(pid: usize, buf: &mut [MapAreaInfo]) -> isize {
+    syscall(
+        SYSCALL_TASK_MAPS,
+        [pid, buf.as_mut_ptr() as usize, buf.len()],
+    )
+}
+
+pub fn sys_meminfo(pid: isize, info: &mut MemInfo) -> isize {
+    syscall(SYSCALL_MEMINFO, [pid as usize, info as *mut _ as usize, 0])
+}
+
+pub fn sys_getrlimit(resource: usize, rlim: &mut RLimit) -> isize {
+    syscall(SYSCALL_GETRLIMIT, [resource, rlim as *mut _ as usize, 0])
+}
+
+pub fn sys_setrlimit(resource: usize, rlim: &RLimit) -> isize {
+    syscall(SYSCALL_SETRLIMIT, [resource, rlim as *const _ as usize, 0])
+}
+
+pub fn sys_trace(pid: isize, enable: bool) -> isize {
+    syscall(SYSCALL_TRACE, [pid as usize, enable as usize, 0])
+}
+
+pub fn sys_trace_read(pid: isize, buf: &mut [TraceEntry]) -> isize {
+    syscall(
+        SYSCALL_TRACE_READ,
+        [pid as usize, buf.as_mut_ptr() as usize, buf.len()],
+    )
+}
+
+pub fn sys_seccomp(prog: &SeccompProg) -> isize {
+    syscall(SYSCALL_SECCOMP, [prog as *const _ as usize, 0, 0])
+}
+
+pub fn sys_proc_list(buf: &mut [ProcInfo]) -> isize {
+    syscall(
+        SYSCALL_PROC_LIST,
+        [buf.as_mut_ptr() as usize, buf.len(), 0],
+    )
+}
+
+pub fn sys_proc_info(pid: usize, info: &mut ProcInfo) -> isize {
+    syscall(SYSCALL_PROC_INFO, [pid, info as *mut _ as usize, 0])
+}
+
+pub fn sys_coredump(pid: usize, offset: usize, buf: &mut [u8]) -> isize {
+    syscall6(
+        SYSCALL_COREDUMP,
+        [pid, offset, buf.as_mut_ptr() as usize, buf.len(), 0, 0],
+    )
+}
+
+/// see [`crate::ptrace`], os5 only
+pub fn sys_ptrace(request: usize, pid: usize, addr: usize, data: usize) -> isize {
+    syscall6(SYSCALL_PTRACE, [request, pid, addr, data, 0, 0])
+}
+
+pub fn sys_ring_setup(entries: usize) -> isize {
+    syscall(SYSCALL_RING_SETUP, [entries, 0, 0])
+}
+
+pub fn sys_ring_enter(to_submit: usize) -> isize {
+    syscall(SYSCALL_RING_ENTER, [to_submit, 0, 0])
+}
+
 pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
     syscall(SYSCALL_THREAD_CREATE, [entry, arg, 0])
 }
@@ -0,0 +1,104 @@
+//! Reading the clock without trapping.
+//!
+//! os5 maps a read-only time page into every process and passes its address
+//! to `_start` in `a2`, which the other kernels leave zero. With the page,
+//! the time since boot is `rdtime` converted with the frequency and boot
+//! offset published there.
+
+use crate::{TimeSpec, TimeVal, CLOCK_MONOTONIC, CLOCK_REALTIME};
+use core::ptr::{addr_of, read_volatile};
+
+const MICRO_PER_SEC: usize = 1_000_000;
+const NANO_PER_SEC: usize = 1_000_000_000;
+
+#[repr(C)]
+struct VdsoData {
+    clock_freq: usize,
+    boot_ticks: usize,
+    realtime_base_ns: usize,
+}
+
+static mut VDSO: usize = 0;
+
+pub(crate) fn init(vdso: usize) {
+    unsafe {
+        VDSO = vdso;
+    }
+}
+
+/// The address of the time page, if the kernel maps one.
+pub fn base() -> Option<usize> {
+    match unsafe { VDSO } {
+        0 => None,
+        vdso => Some(vdso),
+    }
+}
+
+fn data() -> Option<*const VdsoData> {
+    base().map(|vdso| vdso as *const VdsoData)
+}
+
+fn rdtime() -> usize {
+    let time: usize;
+    unsafe {
+        core::arch::asm!("rdtime {}", out(reg) time);
+    }
+    time
+}
+
+/// `time` ticks since boot and their frequency
+fn ticks() -> Option<(usize, usize)> {
+    let data = data()?;
+    let (freq, boot) = unsafe {
+        (
+            read_volatile(addr_of!((*data).clock_freq)),
+            read_volatile(addr_of!((*data).boot_ticks)),
+        )
+    };
+    Some((rdtime() - boot, freq))
+}
+
+/// microseconds since boot, `None` if there is no time page
+pub fn time_us() -> Option<usize> {
+    let (ticks, freq) = ticks()?;
+    Some(ticks / (freq / MICRO_PER_SEC))
+}
+
+/// nanoseconds since boot, `None` if there is no time page
+pub fn time_ns() -> Option<usize> {
+    let (ticks, freq) = ticks()?;
+    Some(ticks * (NANO_PER_SEC / freq))
+}
+
+/// time since boot as `sys_get_time` reports it
+pub fn get_time() -> Option<TimeVal> {
+    let us = time_us()?;
+    Some(TimeVal {
+        sec: us / MICRO_PER_SEC,
+        usec: us % MICRO_PER_SEC,
+    })
+}
+
+/// wall-clock time at boot in nanoseconds since the epoch, `None` if there
+/// is no time page or the kernel does not know
+pub fn realtime_base_ns() -> Option<usize> {
+    let data = data()?;
+    match unsafe { read_volatile(addr_of!((*data).realtime_base_ns)) } {
+        0 => None,
+        base => Some(base),
+    }
+}
+
+/// `CLOCK_REALTIME` and `CLOCK_MONOTONIC` as `sys_clock_gettime` reports
+/// them, `None` for the other clocks
+pub fn clock_gettime(clock_id: usize) -> Option<TimeSpec> {
+    let ns = match clock_id {
+        CLOCK_REALTIME => realtime_base_ns()? + time_ns()?,
+        CLOCK_MONOTONIC => time_ns()?,
+        _ => return None,
+    };
+    Some(TimeSpec {
+        sec: ns / NANO_PER_SEC,
+        nsec: ns % NANO_PER_SEC,
+    })
+}
@@ -0,0 +1,114 @@
+//! Wait statuses, encoded as in POSIX
+//!
+//! The exit code written by `waitpid` is kept as the kernels of every
+//! chapter report it. os5 can also fill an [`ExitInfo`], whose status tells
+//! a normal exit from a kill by the kernel, and which says where a fault hit.
+
+use core::fmt;
+
+pub const SIGILL: i32 = 4;
+pub const SIGTRAP: i32 = 5;
+pub const SIGKILL: i32 = 9;
+pub const SIGSEGV: i32 = 11;
+pub const SIGSTOP: i32 = 19;
+pub const SIGXCPU: i32 = 24;
+pub const SIGSYS: i32 = 31;
+
+/// `scause` of the faults a process can be killed by
+const INSTRUCTION_FAULT: usize = 1;
+const ILLEGAL_INSTRUCTION: usize = 2;
+const LOAD_FAULT: usize = 5;
+const STORE_FAULT: usize = 7;
+const INSTRUCTION_PAGE_FAULT: usize = 12;
+const LOAD_PAGE_FAULT: usize = 13;
+const STORE_PAGE_FAULT: usize = 15;
+
+pub fn wifexited(status: i32) -> bool {
+    status & 0x7f == 0
+}
+
+/// the low 8 bits of the code given to `exit`
+pub fn wexitstatus(status: i32) -> i32 {
+    (status >> 8) & 0xff
+}
+
+pub fn wifsignaled(status: i32) -> bool {
+    status & 0x7f != 0 && !wifstopped(status)
+}
+
+pub fn wtermsig(status: i32) -> i32 {
+    status & 0x7f
+}
+
+pub fn wcoredump(status: i32) -> bool {
+    status & 0x80 != 0
+}
+
+/// whether a traced child has stopped rather than ended
+pub fn wifstopped(status: i32) -> bool {
+    status & 0xff == 0x7f
+}
+
+pub fn wstopsig(status: i32) -> i32 {
+    (status >> 8) & 0xff
+}
+
+pub fn signal_name(signal: i32) -> &'static str {
+    match signal {
+        SIGILL => "SIGILL",
+        SIGTRAP => "SIGTRAP",
+        SIGKILL => "SIGKILL",
+        SIGSEGV => "SIGSEGV",
+        SIGSTOP => "SIGSTOP",
+        SIGXCPU => "SIGXCPU",
+        SIGSYS => "SIGSYS",
+        _ => "unknown signal",
+    }
+}
+
+/// how a child ended, see [`crate::sys::waitpid_info`]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ExitInfo {
+    pub status: i32,
+    /// `scause`, `stval` and `sepc` of the fatal trap, or zero; only `pc`
+    /// for a stop
+    pub cause: usize,
+    pub addr: usize,
+    pub pc: usize,
+}
+
+impl fmt::Display for ExitInfo {
+    /// e.g. "exited with code 0" or "killed by page fault at 0x0"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if wifexited(self.status) {
+            return write!(f, "exited with code {}", wexitstatus(self.status));
+        }
+        if wifstopped(self.status) {
+            let signal = wstopsig(self.status);
+            return write!(f, "stopped by {} at {:#x}", signal_name(signal), self.pc);
+        }
+        match self.cause {
+            _ if !wcoredump(self.status) => {
+                let signal = wtermsig(self.status);
+                write!(f, "killed by {} ({})", signal_name(signal), signal)?;
+            }
+            INSTRUCTION_PAGE_FAULT | LOAD_PAGE_FAULT | STORE_PAGE_FAULT => write!(
+                f,
+                "killed by page fault at {:#x}, pc = {:#x}",
+                self.addr, self.pc
+            )?,
+            INSTRUCTION_FAULT | LOAD_FAULT | STORE_FAULT => write!(
+                f,
+                "killed by access fault at {:#x}, pc = {:#x}",
+                self.addr, self.pc
+            )?,
+            ILLEGAL_INSTRUCTION => write!(f, "killed by illegal instruction at {:#x}", self.pc)?,
+            _ => write!(f, "killed by {}", signal_name(wtermsig(self.status)))?,
+        }
+        if wcoredump(self.status) {
+            write!(f, " (core dumped)")?;
+        }
+        Ok(())
+    }
+}